@@ -1,6 +1,7 @@
 use clap::{Parser, Subcommand};
 
 use crate::{
+    ime::{self, ImeArgs},
     player::{self, PlayerCommands},
     system::{self, SystemCommands},
     tmux::{self, TmuxCommands},
@@ -34,7 +35,13 @@ pub enum Commands {
     },
 
     /// 天气情况
-    #[command(version, about, long_about = None, arg_required_else_help(true))]
+    #[command(
+        version,
+        about,
+        long_about = None,
+        arg_required_else_help(true),
+        subcommand_negates_reqs(true)
+    )]
     Weather(WeatherArgs),
 
     /// Media Info,
@@ -51,6 +58,11 @@ pub enum Commands {
         #[command(subcommand)]
         command: Option<TmuxCommands>,
     },
+
+    /// 输入法状态和切换，
+    /// Power by fcitx5 or ibus with dbus
+    #[command(version, about, long_about, arg_required_else_help(true))]
+    Ime(ImeArgs),
 }
 
 pub fn parse() -> Result<()> {
@@ -71,6 +83,7 @@ pub fn parse() -> Result<()> {
                 Some(cmd) => tmux::parse(cmd),
                 None => Ok(()),
             },
+            Commands::Ime(args) => ime::parse(args),
         },
         None => Ok(()),
     }
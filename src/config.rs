@@ -0,0 +1,90 @@
+use std::{collections::HashMap, env, fs, io, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::Result;
+
+/// 配置文件 `$XDG_CONFIG_HOME/wayinfo/config.toml`
+///
+/// ```toml
+/// [weather.amap]
+/// key-file = "~/.config/apikeys/gaode.txt"
+///
+/// [weather.owm]
+/// key = "..."
+///
+/// [ime.rules]
+/// foot = "keyboard-us"
+///
+/// [[tmux.layouts.default]]
+/// name = "edit"
+/// command = "hx"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// 按数据源名称
+    pub weather: HashMap<String, KeyConfig>,
+    pub ime: ImeConfig,
+    pub tmux: TmuxConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct KeyConfig {
+    pub key: Option<String>,
+    pub key_file: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ImeConfig {
+    /// app_id 使用的输入法，`ime follow-focus` 时总是切换到该输入法
+    pub rules: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TmuxConfig {
+    /// 按项目名称的窗口，没有时使用 `default`
+    pub layouts: HashMap<String, Vec<WindowConfig>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub name: Option<String>,
+    /// 在窗口的 shell 中执行
+    pub command: Option<String>,
+}
+
+impl Config {
+    /// 文件不存在时为默认配置
+    pub fn load() -> Result<Config> {
+        let path = path();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(toml::from_str(&text)?)
+    }
+}
+
+pub fn path() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join("wayinfo")
+        .join("config.toml")
+}
+
+/// 展开开头的 `~/`
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
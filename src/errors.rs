@@ -3,6 +3,7 @@ use std::io;
 use reqwest::StatusCode;
 
 #[derive(Debug, thiserror::Error, miette::Diagnostic)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     // #[error(transparent)]
     // Tr(#[from] serde_json::Error),
@@ -15,22 +16,46 @@ pub enum Error {
     #[error(transparent)]
     DBus(#[from] dbus::Error),
 
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
+    #[error("Invalid argument: {msg}")]
+    Args { msg: String },
+
     #[error("Player Get failed: {msg}")]
     Player { msg: String },
 
+    #[error("System failed: {msg}")]
+    System { msg: String },
+
+    #[error("Tmux failed with status {status}: {stderr}")]
+    Tmux { status: i32, stderr: String },
+
+    #[error("Zellij failed: {msg}")]
+    Zellij { msg: String },
+
+    #[error("{program} failed: {stderr}")]
+    Editor { program: String, stderr: String },
+
+    #[error("Input method failed: {msg}")]
+    Ime { msg: String },
+
     // #[error(transparent)]
     // ValidatorError(#[from] validator::ValidationError),
 
     // #[error("unknown")]
     // Unknown,
-    #[error("Weather need key of api.")]
-    WeatherKeyError,
+    #[error("Weather need key of api, tried: {tried}.")]
+    WeatherKeyError { tried: String },
 
     #[error("Weather get fail.")]
     WeatherFailError,
 
     #[error("Weather Request failed:{code}")]
     WeatherResponseError { code: StatusCode },
+
+    #[error("Weather provider does not support {what}.")]
+    WeatherUnsupportedError { what: &'static str },
 }
 
 impl<T> From<Error> for crate::Result<T> {
@@ -1,8 +1,282 @@
 #[cfg(target_os = "linux")]
 mod fcitx;
 
+#[cfg(target_os = "linux")]
+mod focus;
+
 #[cfg(target_os = "linux")]
 mod ibus;
 
 #[cfg(target_os = "macos")]
 mod macime;
+
+#[cfg(all(test, target_os = "linux"))]
+mod mock;
+
+use std::{
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use clap::{Args, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::{errors::Error, utils::parse_duration, waybar::WaybarData, Result};
+
+#[derive(Args)]
+pub struct ImeArgs {
+    #[command(subcommand)]
+    pub command: ImeCommands,
+
+    /// 输入法框架，auto 时优先使用正在运行的 fcitx5
+    #[arg(short, long, value_enum, default_value_t = BackendKind::Auto)]
+    pub backend: BackendKind,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum BackendKind {
+    Auto,
+    Fcitx,
+    Ibus,
+}
+
+#[derive(Subcommand)]
+pub enum ImeCommands {
+    /// 当前输入法名称和标签
+    Current,
+    /// 切换输入状态，如中英文
+    Toggle,
+    /// 切换到输入法
+    Switch {
+        /// 输入法名称，如 `pinyin`, `keyboard-us`
+        name: String,
+    },
+    /// 当前分组的输入法
+    List,
+    /// waybar format
+    Waybar(ImeWaybarArgs),
+    /// 持续运行，按窗口 app_id 记住和恢复输入法，支持 sway、niri 和 hyprland
+    FollowFocus,
+}
+
+#[derive(Args)]
+pub struct ImeWaybarArgs {
+    /// 显示输入法标签，默认为 `中`/`EN`
+    #[arg(long)]
+    pub label: bool,
+
+    /// 持续运行，输入法变化时输出一行
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// follow 的检查间隔，如 500ms, 1s
+    #[arg(long, value_parser = parse_duration, default_value = "500ms")]
+    pub interval: Duration,
+}
+
+/// 输入法
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputMethod {
+    /// 唯一名称，如 `pinyin`
+    pub name: String,
+    /// 简短标签，如 `拼`
+    pub label: String,
+    /// 显示名称，如 `拼音`
+    pub display: String,
+}
+
+/// 输入法框架
+trait Backend {
+    /// 当前输入法
+    fn current(&self) -> Result<InputMethod>;
+
+    /// 是否为输入状态，键盘布局为非输入状态
+    fn active(&self) -> Result<bool>;
+
+    fn toggle(&self) -> Result<()>;
+
+    fn switch(&self, name: &str) -> Result<()>;
+
+    /// 当前分组中的输入法
+    fn list(&self) -> Result<Vec<InputMethod>>;
+}
+
+#[cfg(target_os = "linux")]
+fn backend(kind: BackendKind) -> Result<Box<dyn Backend>> {
+    match kind {
+        BackendKind::Fcitx => Ok(Box::new(fcitx::Fcitx::new()?)),
+        BackendKind::Ibus => Ok(Box::new(ibus::Ibus::new()?)),
+        // 没有 session bus 时 fcitx5 视为没有运行，ibus 使用自己的总线
+        BackendKind::Auto => {
+            match fcitx::Fcitx::new().and_then(|f| Ok((f.running()?, f))) {
+                Ok((true, fcitx)) => return Ok(Box::new(fcitx)),
+                Ok((false, _)) => {}
+                Err(err) => log::info!("fcitx5 unavailable: {}", err),
+            }
+
+            match ibus::Ibus::new() {
+                Ok(ibus) => Ok(Box::new(ibus)),
+                Err(err) => Error::Ime {
+                    msg: format!("neither fcitx5 nor ibus is running ({err})"),
+                }
+                .into(),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn backend(_kind: BackendKind) -> Result<Box<dyn Backend>> {
+    Error::Ime {
+        msg: "no input method backend on this platform".to_owned(),
+    }
+    .into()
+}
+
+pub fn parse(args: &ImeArgs) -> Result<()> {
+    let backend = backend(args.backend)?;
+
+    match &args.command {
+        ImeCommands::Current => {
+            let im = backend.current()?;
+            println!("{}\t{}", im.name, im.label);
+        }
+        ImeCommands::Toggle => backend.toggle()?,
+        ImeCommands::Switch { name } => {
+            let list = backend.list()?;
+            if !list.iter().any(|im| &im.name == name) {
+                return Error::Ime {
+                    msg: format!("input method {name} not found"),
+                }
+                .into();
+            }
+            backend.switch(name)?;
+        }
+        ImeCommands::List => {
+            for im in backend.list()? {
+                println!("{}\t{}\t{}", im.name, im.label, im.display);
+            }
+        }
+        ImeCommands::Waybar(args) => waybar(backend.as_ref(), args)?,
+        ImeCommands::FollowFocus => follow_focus(backend.as_ref())?,
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn follow_focus(backend: &dyn Backend) -> Result<()> {
+    focus::follow(backend, &crate::config::Config::load()?.ime)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn follow_focus(_backend: &dyn Backend) -> Result<()> {
+    Error::Ime {
+        msg: "follow-focus is not supported on this platform".to_owned(),
+    }
+    .into()
+}
+
+/// follow 时只在变化时输出
+fn waybar(backend: &dyn Backend, args: &ImeWaybarArgs) -> Result<()> {
+    if !args.follow {
+        let re = serde_json::to_string(&waybar_data(backend, args)?).unwrap();
+        println!("{re}");
+        return Ok(());
+    }
+
+    let mut last = String::new();
+    loop {
+        // 输入法框架重启时不退出
+        let data = waybar_data(backend, args).unwrap_or_else(|err| WaybarData {
+            class: "wayinfo-ime off".to_owned(),
+            text: String::new(),
+            alt: None,
+            tooltip: Some(err.to_string()),
+            percentage: None,
+        });
+
+        let re = serde_json::to_string(&data).unwrap();
+        if re != last {
+            println!("{re}");
+            io::stdout().flush()?;
+            last = re;
+        }
+
+        thread::sleep(args.interval);
+    }
+}
+
+/// class 为 `active` 或 `inactive`，alt 为输入法名称
+fn waybar_data(backend: &dyn Backend, args: &ImeWaybarArgs) -> Result<WaybarData> {
+    let im = backend.current()?;
+    let active = backend.active()? && !is_layout(&im.name);
+
+    let text = match (args.label, active) {
+        (true, _) => im.label.clone(),
+        (false, true) => "中".to_owned(),
+        (false, false) => "EN".to_owned(),
+    };
+
+    Ok(WaybarData {
+        class: format!("wayinfo-ime {}", if active { "active" } else { "inactive" }),
+        text,
+        alt: Some(im.name),
+        tooltip: Some(im.display),
+        percentage: None,
+    })
+}
+
+/// 键盘布局，如 fcitx5 的 `keyboard-us`，ibus 的 `xkb:us::eng`
+fn is_layout(name: &str) -> bool {
+    name.starts_with("keyboard-") || name.starts_with("xkb:")
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use mock::Bus;
+
+    fn args(label: bool) -> ImeWaybarArgs {
+        ImeWaybarArgs {
+            label,
+            follow: false,
+            interval: Duration::from_millis(500),
+        }
+    }
+
+    #[test]
+    fn waybar() {
+        let Some(bus) = Bus::start() else {
+            return;
+        };
+        mock::fcitx(&bus);
+        let backend = fcitx::Fcitx::from(bus.connect());
+
+        let data = waybar_data(&backend, &args(false)).unwrap();
+        assert_eq!(data.text, "EN");
+        assert_eq!(data.class, "wayinfo-ime inactive");
+        assert_eq!(data.alt.as_deref(), Some("keyboard-us"));
+        assert_eq!(data.tooltip.as_deref(), Some("English (US)"));
+
+        backend.switch("pinyin").unwrap();
+        let data = waybar_data(&backend, &args(false)).unwrap();
+        assert_eq!(data.text, "中");
+        assert_eq!(data.class, "wayinfo-ime active");
+
+        let data = waybar_data(&backend, &args(true)).unwrap();
+        assert_eq!(data.text, "拼");
+        assert_eq!(
+            serde_json::to_string(&data).unwrap(),
+            r#"{"class":["wayinfo-ime","active"],"text":"拼","alt":"pinyin","tooltip":"拼音"}"#
+        );
+    }
+
+    #[test]
+    fn layouts() {
+        assert!(is_layout("keyboard-us"));
+        assert!(is_layout("xkb:us::eng"));
+        assert!(!is_layout("pinyin"));
+    }
+}
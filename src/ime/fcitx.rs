@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use dbus::blocking::{Connection, Proxy};
+
+use super::{Backend, InputMethod};
+use crate::Result;
+
+const DBUS_FCITX: &str = "org.fcitx.Fcitx5";
+const DBUS_PATH: &str = "/controller";
+const DBUS_CONTROLLER: &str = "org.fcitx.Fcitx.Controller1";
+
+/// 唯一名称、名称、本地名称、图标、标签、语言、可配置
+type Available = (String, String, String, String, String, String, bool);
+
+/// fcitx5 controller
+pub struct Fcitx {
+    conn: Connection,
+}
+
+impl Fcitx {
+    pub fn new() -> Result<Fcitx> {
+        let conn = Connection::new_session()?;
+        Ok(Fcitx { conn })
+    }
+
+    /// session bus 上是否有 fcitx5
+    pub fn running(&self) -> Result<bool> {
+        let (has_owner,): (bool,) = self
+            .conn
+            .with_proxy(
+                "org.freedesktop.DBus",
+                "/org/freedesktop/DBus",
+                Duration::from_millis(2000),
+            )
+            .method_call("org.freedesktop.DBus", "NameHasOwner", (DBUS_FCITX,))?;
+        Ok(has_owner)
+    }
+
+    fn proxy(&self) -> Proxy<'_, &Connection> {
+        self.conn
+            .with_proxy(DBUS_FCITX, DBUS_PATH, Duration::from_millis(2000))
+    }
+
+    /// 所有已安装的输入法
+    fn available(&self) -> Result<Vec<Available>> {
+        let (list,): (Vec<Available>,) =
+            self.proxy()
+                .method_call(DBUS_CONTROLLER, "AvailableInputMethods", ())?;
+        Ok(list)
+    }
+
+    /// 没有找到时标签和显示名称都为 `name`
+    fn input_method(available: &[Available], name: &str) -> InputMethod {
+        match available.iter().find(|im| im.0 == name) {
+            Some((name, display, _, _, label, _, _)) => InputMethod {
+                name: name.clone(),
+                label: label.clone(),
+                display: display.clone(),
+            },
+            None => InputMethod {
+                name: name.to_owned(),
+                label: name.to_owned(),
+                display: name.to_owned(),
+            },
+        }
+    }
+}
+
+/// 测试时连接到私有总线
+#[cfg(test)]
+impl From<Connection> for Fcitx {
+    fn from(conn: Connection) -> Self {
+        Fcitx { conn }
+    }
+}
+
+impl Backend for Fcitx {
+    fn current(&self) -> Result<InputMethod> {
+        let (name,): (String,) =
+            self.proxy()
+                .method_call(DBUS_CONTROLLER, "CurrentInputMethod", ())?;
+        Ok(Fcitx::input_method(&self.available()?, &name))
+    }
+
+    /// 0 关闭，1 未激活，2 激活
+    fn active(&self) -> Result<bool> {
+        let (state,): (i32,) = self.proxy().method_call(DBUS_CONTROLLER, "State", ())?;
+        Ok(state == 2)
+    }
+
+    fn toggle(&self) -> Result<()> {
+        let (): () = self.proxy().method_call(DBUS_CONTROLLER, "Toggle", ())?;
+        Ok(())
+    }
+
+    fn switch(&self, name: &str) -> Result<()> {
+        let (): () = self
+            .proxy()
+            .method_call(DBUS_CONTROLLER, "SetCurrentIM", (name,))?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<InputMethod>> {
+        let proxy = self.proxy();
+        let (group,): (String,) =
+            proxy.method_call(DBUS_CONTROLLER, "CurrentInputMethodGroup", ())?;
+        // 默认布局和 (输入法, 布局)
+        let (_layout, items): (String, Vec<(String, String)>) =
+            proxy.method_call(DBUS_CONTROLLER, "InputMethodGroupInfo", (group,))?;
+
+        let available = self.available()?;
+        Ok(items
+            .iter()
+            .map(|(name, _)| Fcitx::input_method(&available, name))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ime::mock::{self, Bus};
+
+    fn fcitx(bus: &Bus) -> Fcitx {
+        mock::fcitx(bus);
+        Fcitx::from(bus.connect())
+    }
+
+    #[test]
+    fn current_and_list() {
+        let Some(bus) = Bus::start() else {
+            return;
+        };
+        let fcitx = fcitx(&bus);
+
+        assert!(fcitx.running().unwrap());
+        let im = fcitx.current().unwrap();
+        assert_eq!(im.name, "keyboard-us");
+        assert_eq!(im.label, "us");
+        assert_eq!(im.display, "English (US)");
+        assert!(!fcitx.active().unwrap());
+
+        // 只有当前分组，不包括 rime
+        let names: Vec<String> = fcitx
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|im| im.name)
+            .collect();
+        assert_eq!(names, ["keyboard-us", "pinyin"]);
+    }
+
+    #[test]
+    fn toggle_and_switch() {
+        let Some(bus) = Bus::start() else {
+            return;
+        };
+        let fcitx = fcitx(&bus);
+
+        fcitx.toggle().unwrap();
+        assert!(fcitx.active().unwrap());
+        assert_eq!(fcitx.current().unwrap().label, "拼");
+
+        fcitx.switch("keyboard-us").unwrap();
+        assert!(!fcitx.active().unwrap());
+        assert_eq!(fcitx.current().unwrap().name, "keyboard-us");
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+use serde_json::Value;
+
+use super::Backend;
+use crate::{config::ImeConfig, errors::Error, Result};
+
+/// 窗口焦点变化
+trait FocusSource {
+    /// 阻塞直到焦点变化，返回新窗口的 app_id
+    fn next(&mut self) -> Result<Option<String>>;
+}
+
+/// 根据环境变量选择 sway、niri 或 hyprland
+fn source() -> Result<Box<dyn FocusSource>> {
+    if let Ok(path) = env::var("SWAYSOCK") {
+        return Ok(Box::new(Sway::new(&path)?));
+    }
+    if let Ok(path) = env::var("NIRI_SOCKET") {
+        return Ok(Box::new(Niri::new(&path)?));
+    }
+    if let Ok(signature) = env::var("HYPRLAND_INSTANCE_SIGNATURE") {
+        return Ok(Box::new(Hyprland::new(&signature)?));
+    }
+
+    Error::Ime {
+        msg: "no sway, niri or hyprland IPC socket found".to_owned(),
+    }
+    .into()
+}
+
+/// 切换窗口时记住上一个窗口的输入法，恢复新窗口的输入法，
+/// 配置中的规则优先
+pub(super) fn follow(backend: &dyn Backend, config: &ImeConfig) -> Result<()> {
+    let mut source = source()?;
+    let mut memory: HashMap<String, String> = HashMap::new();
+    let mut last: Option<String> = None;
+
+    loop {
+        let Some(app) = source.next()? else {
+            continue;
+        };
+        if last.as_deref() == Some(app.as_str()) {
+            continue;
+        }
+
+        // 输入法框架重启时不退出
+        let current = match backend.current() {
+            Ok(im) => im.name,
+            Err(err) => {
+                log::warn!("Failed to get input method: {}", err);
+                last = Some(app);
+                continue;
+            }
+        };
+        if let Some(prev) = last.replace(app.clone()) {
+            memory.insert(prev, current.clone());
+        }
+
+        let target = config.rules.get(&app).or_else(|| memory.get(&app));
+        if let Some(name) = target.filter(|name| **name != current) {
+            log::info!("{app}: switch to {name}");
+            if let Err(err) = backend.switch(name) {
+                log::warn!("Failed to switch input method: {}", err);
+            }
+        }
+    }
+}
+
+/// sway i3-ipc，订阅 window 事件
+struct Sway {
+    stream: UnixStream,
+}
+
+impl Sway {
+    const MAGIC: &'static [u8] = b"i3-ipc";
+    const SUBSCRIBE: u32 = 2;
+
+    fn new(path: &str) -> Result<Sway> {
+        let mut sway = Sway {
+            stream: UnixStream::connect(path)?,
+        };
+        sway.send(Sway::SUBSCRIBE, r#"["window"]"#)?;
+        // {"success": true}
+        sway.read()?;
+        Ok(sway)
+    }
+
+    fn send(&mut self, kind: u32, payload: &str) -> Result<()> {
+        let mut message = Sway::MAGIC.to_vec();
+        message.extend((payload.len() as u32).to_ne_bytes());
+        message.extend(kind.to_ne_bytes());
+        message.extend(payload.as_bytes());
+        self.stream.write_all(&message)?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<Value> {
+        let mut header = [0u8; 14];
+        self.stream.read_exact(&mut header)?;
+        let len = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+
+        let mut payload = vec![0u8; len as usize];
+        self.stream.read_exact(&mut payload)?;
+        Ok(serde_json::from_slice(&payload)?)
+    }
+}
+
+impl FocusSource for Sway {
+    /// xwayland 窗口没有 app_id，使用 class
+    fn next(&mut self) -> Result<Option<String>> {
+        let event = self.read()?;
+        if event["change"] != "focus" {
+            return Ok(None);
+        }
+
+        let container = &event["container"];
+        let app = container["app_id"]
+            .as_str()
+            .or_else(|| container["window_properties"]["class"].as_str());
+        Ok(app.map(str::to_owned))
+    }
+}
+
+/// niri 事件流，焦点事件只有窗口 id，需要记录窗口的 app_id
+struct Niri {
+    reader: BufReader<UnixStream>,
+    windows: HashMap<u64, String>,
+}
+
+impl Niri {
+    fn new(path: &str) -> Result<Niri> {
+        let mut stream = UnixStream::connect(path)?;
+        stream.write_all(b"\"EventStream\"\n")?;
+
+        let mut niri = Niri {
+            reader: BufReader::new(stream),
+            windows: HashMap::new(),
+        };
+        // {"Ok":"Handled"}
+        niri.read()?;
+        Ok(niri)
+    }
+
+    fn read(&mut self) -> Result<Value> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Error::Ime {
+                msg: "niri closed the event stream".to_owned(),
+            }
+            .into();
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    fn insert(&mut self, window: &Value) {
+        if let (Some(id), Some(app)) = (window["id"].as_u64(), window["app_id"].as_str()) {
+            self.windows.insert(id, app.to_owned());
+        }
+    }
+}
+
+impl FocusSource for Niri {
+    fn next(&mut self) -> Result<Option<String>> {
+        let event = self.read()?;
+
+        if let Some(windows) = event["WindowsChanged"]["windows"].as_array() {
+            self.windows.clear();
+            for window in windows {
+                self.insert(window);
+            }
+        } else if let Some(window) = event.get("WindowOpenedOrChanged") {
+            self.insert(&window["window"]);
+        } else if let Some(id) = event["WindowClosed"]["id"].as_u64() {
+            self.windows.remove(&id);
+        } else if let Some(id) = event["WindowFocusChanged"]["id"].as_u64() {
+            return Ok(self.windows.get(&id).cloned());
+        }
+
+        Ok(None)
+    }
+}
+
+/// hyprland socket2 事件，如 `activewindow>>foot,title`
+struct Hyprland {
+    reader: BufReader<UnixStream>,
+}
+
+impl Hyprland {
+    fn new(signature: &str) -> Result<Hyprland> {
+        let runtime = env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        // 旧版本在 /tmp/hypr
+        let path = [runtime.join("hypr"), PathBuf::from("/tmp/hypr")]
+            .into_iter()
+            .map(|dir| dir.join(signature).join(".socket2.sock"))
+            .find(|path| path.exists())
+            .unwrap_or_default();
+
+        Ok(Hyprland {
+            reader: BufReader::new(UnixStream::connect(path)?),
+        })
+    }
+}
+
+impl FocusSource for Hyprland {
+    fn next(&mut self) -> Result<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Error::Ime {
+                msg: "hyprland closed the event socket".to_owned(),
+            }
+            .into();
+        }
+
+        let app = line
+            .trim_end()
+            .strip_prefix("activewindow>>")
+            .and_then(|data| data.split(',').next())
+            .filter(|class| !class.is_empty());
+        Ok(app.map(str::to_owned))
+    }
+}
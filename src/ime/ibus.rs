@@ -0,0 +1,176 @@
+use std::{env, fs, path::PathBuf, time::Duration};
+
+use dbus::{
+    arg::{ArgType, RefArg, Variant},
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection, Proxy},
+    channel::Channel,
+};
+
+use super::{Backend, InputMethod};
+use crate::{errors::Error, Result};
+
+const DBUS_IBUS: &str = "org.freedesktop.IBus";
+const DBUS_PATH: &str = "/org/freedesktop/IBus";
+
+type Value = Variant<Box<dyn RefArg>>;
+
+/// ibus-daemon，使用 ibus 私有总线
+pub struct Ibus {
+    conn: Connection,
+}
+
+impl Ibus {
+    pub fn new() -> Result<Ibus> {
+        let mut channel = Channel::open_private(&address()?)?;
+        channel.register()?;
+        Ok(Ibus {
+            conn: Connection::from(channel),
+        })
+    }
+
+    fn proxy(&self) -> Proxy<'_, &Connection> {
+        self.conn
+            .with_proxy(DBUS_IBUS, DBUS_PATH, Duration::from_millis(2000))
+    }
+
+    fn global_engine(&self) -> Result<InputMethod> {
+        let value: Value = self.proxy().get(DBUS_IBUS, "GlobalEngine")?;
+        match engine(&value) {
+            Some(im) => Ok(im),
+            None => Error::Ime {
+                msg: "no global engine".to_owned(),
+            }
+            .into(),
+        }
+    }
+}
+
+impl Backend for Ibus {
+    fn current(&self) -> Result<InputMethod> {
+        self.global_engine()
+    }
+
+    fn active(&self) -> Result<bool> {
+        Ok(!self.global_engine()?.name.starts_with("xkb:"))
+    }
+
+    /// ibus 没有中英文状态，在键盘布局和第一个输入法之间切换
+    fn toggle(&self) -> Result<()> {
+        let layout = !self.active()?;
+        let list = self.list()?;
+
+        match list.iter().find(|im| im.name.starts_with("xkb:") != layout) {
+            Some(im) => self.switch(&im.name),
+            None => Error::Ime {
+                msg: "no engine to toggle".to_owned(),
+            }
+            .into(),
+        }
+    }
+
+    fn switch(&self, name: &str) -> Result<()> {
+        let (): () = self
+            .proxy()
+            .method_call(DBUS_IBUS, "SetGlobalEngine", (name,))?;
+        Ok(())
+    }
+
+    /// 已启用的输入法，旧版本没有时为所有输入法
+    fn list(&self) -> Result<Vec<InputMethod>> {
+        let proxy = self.proxy();
+        let engines: Vec<Value> = match proxy.get(DBUS_IBUS, "ActiveEngines") {
+            Ok(engines) => engines,
+            Err(_) => proxy.get(DBUS_IBUS, "Engines")?,
+        };
+
+        Ok(engines.iter().filter_map(|e| engine(e)).collect())
+    }
+}
+
+/// 解析 IBusEngineDesc：
+/// 类型名、附加数据、name、longname、description、language、license、
+/// author、icon、layout、rank、hotkeys、symbol ..
+fn engine(value: &dyn RefArg) -> Option<InputMethod> {
+    let mut value = value;
+    while value.arg_type() == ArgType::Variant {
+        value = value.as_iter()?.next()?;
+    }
+
+    let fields: Vec<&dyn RefArg> = value.as_iter()?.collect();
+    let field = |i: usize| fields.get(i).and_then(|f| f.as_str()).unwrap_or_default();
+
+    let name = field(2);
+    if name.is_empty() {
+        return None;
+    }
+
+    // 没有 symbol 时使用语言
+    let label = match field(12) {
+        "" => field(5),
+        symbol => symbol,
+    };
+
+    Some(InputMethod {
+        name: name.to_owned(),
+        label: label.to_owned(),
+        display: field(3).to_owned(),
+    })
+}
+
+/// `IBUS_ADDRESS`，或者 `~/.config/ibus/bus/` 中 ibus-daemon 写入的地址
+fn address() -> Result<String> {
+    if let Ok(address) = env::var("IBUS_ADDRESS") {
+        return Ok(address);
+    }
+
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join("ibus")
+        .join("bus");
+
+    // 文件名为 `<machine-id>-<host>-<display>`，不确定显示器时使用最新的
+    let mut files: Vec<_> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    files.sort();
+
+    let display = env::var("WAYLAND_DISPLAY")
+        .or_else(|_| env::var("DISPLAY").map(|d| display_number(&d)))
+        .unwrap_or_default();
+    let path = files
+        .iter()
+        .map(|(_, path)| path)
+        .find(|path| {
+            !display.is_empty()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.ends_with(&format!("-{display}")))
+        })
+        .or(files.last().map(|(_, path)| path));
+
+    let text = match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => String::new(),
+    };
+
+    match text
+        .lines()
+        .find_map(|line| line.strip_prefix("IBUS_ADDRESS="))
+    {
+        Some(address) => Ok(address.trim().to_owned()),
+        None => Error::Ime {
+            msg: format!("ibus address not found in {}", dir.display()),
+        }
+        .into(),
+    }
+}
+
+/// `:0.0` 中的 `0`
+fn display_number(display: &str) -> String {
+    let number = display.rsplit_once(':').map_or(display, |(_, n)| n);
+    number.split('.').next().unwrap_or_default().to_owned()
+}
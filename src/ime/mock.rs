@@ -0,0 +1,140 @@
+//! 测试用的私有 dbus-daemon 和 fcitx5 controller
+
+use std::{
+    env,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use dbus::{blocking::Connection, channel::Channel};
+use dbus_crossroads::Crossroads;
+
+/// 私有总线，drop 时结束
+pub(super) struct Bus {
+    daemon: Child,
+    pub address: String,
+}
+
+impl Bus {
+    /// 没有 dbus-daemon 时为 None，测试跳过。
+    /// 设置了 `CI` 时失败，避免没有运行的测试显示为通过
+    pub fn start() -> Option<Bus> {
+        let mut daemon = match Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(err) if env::var_os("CI").is_some() => {
+                panic!("dbus-daemon is required when CI is set: {err}")
+            }
+            Err(err) => {
+                eprintln!("skip: dbus-daemon not available ({err})");
+                return None;
+            }
+        };
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+
+        Some(Bus {
+            daemon,
+            address: address.trim().to_owned(),
+        })
+    }
+
+    pub fn connect(&self) -> Connection {
+        let mut channel = Channel::open_private(&self.address).unwrap();
+        channel.register().unwrap();
+        Connection::from(channel)
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// 当前输入法和状态
+struct State {
+    im: String,
+    state: i32,
+}
+
+/// 在后台线程中提供 org.fcitx.Fcitx5，分组为 `keyboard-us` 和 `pinyin`
+pub(super) fn fcitx(bus: &Bus) {
+    let conn = bus.connect();
+    conn.request_name("org.fcitx.Fcitx5", false, true, false)
+        .unwrap();
+
+    thread::spawn(move || {
+        let mut cr = Crossroads::new();
+        let state = Arc::new(Mutex::new(State {
+            im: "keyboard-us".to_owned(),
+            state: 1,
+        }));
+
+        let iface = cr.register(
+            "org.fcitx.Fcitx.Controller1",
+            |b: &mut dbus_crossroads::IfaceBuilder<Arc<Mutex<State>>>| {
+                b.method("CurrentInputMethod", (), ("name",), |_, s, ()| {
+                    Ok((s.lock().unwrap().im.clone(),))
+                });
+                b.method("State", (), ("state",), |_, s, ()| {
+                    Ok((s.lock().unwrap().state,))
+                });
+                b.method("Toggle", (), (), |_, s, ()| {
+                    let mut s = s.lock().unwrap();
+                    let (im, state) = match s.state {
+                        2 => ("keyboard-us", 1),
+                        _ => ("pinyin", 2),
+                    };
+                    s.im = im.to_owned();
+                    s.state = state;
+                    Ok(())
+                });
+                b.method("SetCurrentIM", ("name",), (), |_, s, (name,): (String,)| {
+                    let mut s = s.lock().unwrap();
+                    s.state = if name.starts_with("keyboard-") { 1 } else { 2 };
+                    s.im = name;
+                    Ok(())
+                });
+                b.method("CurrentInputMethodGroup", (), ("group",), |_, _, ()| {
+                    Ok(("Default".to_owned(),))
+                });
+                b.method(
+                    "InputMethodGroupInfo",
+                    ("group",),
+                    ("layout", "items"),
+                    |_, _, (_group,): (String,)| {
+                        let items = vec![
+                            ("keyboard-us".to_owned(), String::new()),
+                            ("pinyin".to_owned(), String::new()),
+                        ];
+                        Ok(("us".to_owned(), items))
+                    },
+                );
+                b.method("AvailableInputMethods", (), ("list",), |_, _, ()| {
+                    let im = |name: &str, display: &str, label: &str, lang: &str, conf| {
+                        let s = str::to_owned;
+                        (s(name), s(display), s(""), s(""), s(label), s(lang), conf)
+                    };
+                    Ok((vec![
+                        im("keyboard-us", "English (US)", "us", "en", false),
+                        im("pinyin", "拼音", "拼", "zh_CN", true),
+                        im("rime", "中州韻", "ㄓ", "zh", true),
+                    ],))
+                });
+            },
+        );
+        cr.insert("/controller", &[iface], state);
+        let _ = cr.serve(&conn);
+    });
+}
@@ -496,7 +496,7 @@ impl PlayerClient {
             self.set_property_string(PlayerProperty::LoopStatus.into(), mode.into())?;
         }
 
-        let status = self.get_property_string(PlayerProperty::LoopStatus.into())?;
+        let status = self.get_property_string(PlayerProperty::LoopStatus)?;
 
         println!("{status}");
 
@@ -1,14 +1,98 @@
-use clap::{Args, Subcommand};
-use sysinfo::System;
+mod disk;
+mod history;
+mod info;
+mod network;
+mod process;
+mod threshold;
 
-use crate::Result;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use sysinfo::{
+    Disks, Networks, ProcessRefreshKind, System, UpdateKind, MINIMUM_CPU_UPDATE_INTERVAL,
+};
+
+use crate::{utils::parse_duration, waybar::WaybarData, Result};
+
+pub use disk::SysDiskArgs;
+use history::{sparkline, Counters, History, HistoryArgs};
+pub use info::SysInfoArgs;
+pub use network::SysNetArgs;
+pub use process::{SysKillArgs, SysTopArgs};
+use threshold::{Level, ThresholdArgs};
 
 #[derive(Subcommand)]
 pub enum SystemCommands {
-    Cpu,
-    Cpus,
+    /// global cpu usage
+    Cpu(SysCpuArgs),
+    /// usage of every cpu core
+    Cpus(SysCpusArgs),
+    /// memory usage
     Memory(SysMemArgs),
-    Disk,
+    /// disk usage of a mount point
+    Disk(SysDiskArgs),
+    /// network throughput
+    Network(SysNetArgs),
+    /// top processes sorted by cpu or memory
+    Top(SysTopArgs),
+    /// kill processes by pid or name
+    Kill(SysKillArgs),
+    /// host, kernel, uptime, load and users
+    Info(SysInfoArgs),
+}
+
+/// Stdout format of system commands
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// plain text or table
+    Text,
+    /// json
+    Json,
+    /// waybar custom module json
+    Waybar,
+}
+
+/// Output options shared by system commands
+#[derive(Args)]
+pub struct StdoutArgs {
+    /// stdout format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// keep running and print a line every interval
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// interval of follow mode, such as 500ms, 2s, 1m
+    #[arg(long, value_parser = parse_duration, default_value = "2s")]
+    pub interval: Duration,
+}
+
+#[derive(Args)]
+pub struct SysCpuArgs {
+    #[command(flatten)]
+    pub stdout: StdoutArgs,
+
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    #[command(flatten)]
+    pub threshold: ThresholdArgs,
+}
+
+#[derive(Args)]
+pub struct SysCpusArgs {
+    #[command(flatten)]
+    pub stdout: StdoutArgs,
+
+    #[command(flatten)]
+    pub threshold: ThresholdArgs,
 }
 
 #[derive(Args)]
@@ -20,49 +104,302 @@ pub struct SysMemArgs {
     //// used memory
     #[arg(short, long)]
     pub usage: bool,
+
+    #[command(flatten)]
+    pub stdout: StdoutArgs,
+
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    #[command(flatten)]
+    pub threshold: ThresholdArgs,
 }
 
-pub fn parse(cmd: &SystemCommands) -> Result<()> {
-    let mut data = Data::new();
+/// A single sample of a metric, printed in every output format
+struct Reading {
+    class: &'static str,
+    icon: &'static str,
+    /// value kept in history
+    value: f64,
+    /// fixed top of the sparkline scale
+    max: Option<f64>,
+    /// format `value` for min/avg/max
+    unit: fn(f64) -> String,
+    text: String,
+    tooltip: Vec<String>,
+    percentage: Option<usize>,
+    json: Value,
+    /// state of thresholds, added to the class
+    level: Option<Level>,
+}
+
+impl Reading {
+    fn print(mut self, output: OutputFormat, history: Option<&mut History>) {
+        let mut spark = None;
+
+        if let (Value::Object(map), Some(level)) = (&mut self.json, self.level) {
+            map.insert("state".to_owned(), json!(level));
+        }
+
+        if let Some(history) = history {
+            history.push(self.value);
+            history.save();
+
+            let sparkline = history.sparkline(self.max);
+            if let Some(summary) = history.summary(self.unit) {
+                self.tooltip.push(format!("{sparkline} {summary}"));
+            }
+            if let (Value::Object(map), Some((min, avg, max))) = (&mut self.json, history.stats()) {
+                map.insert("sparkline".to_owned(), json!(sparkline));
+                map.insert("min".to_owned(), json!(min));
+                map.insert("avg".to_owned(), json!(avg));
+                map.insert("max".to_owned(), json!(max));
+            }
+            spark = Some(sparkline);
+        }
+
+        match output {
+            OutputFormat::Text => match spark {
+                Some(spark) => println!("{} {}", self.text, spark),
+                None => println!("{}", self.text),
+            },
+            OutputFormat::Json => {
+                let re = serde_json::to_string(&self.json).unwrap();
+                println!("{re}");
+            }
+            OutputFormat::Waybar => {
+                let text = match spark {
+                    Some(spark) => format!("{} {} {}", self.icon, spark, self.text),
+                    None => format!("{} {}", self.icon, self.text),
+                };
+
+                let class = match self.level {
+                    Some(level) => format!("{} {}", self.class, level.as_str()),
+                    None => self.class.to_owned(),
+                };
+
+                let data = WaybarData {
+                    class,
+                    text,
+                    alt: None,
+                    tooltip: Some(self.tooltip.join("\n")),
+                    percentage: self.percentage,
+                };
+                let re = serde_json::to_string(&data).unwrap();
+                println!("{re}");
+            }
+        }
+    }
+}
 
+pub fn parse(cmd: &SystemCommands) -> Result<()> {
     match cmd {
-        SystemCommands::Cpu => data.cpu(),
-        SystemCommands::Cpus => todo!(),
-        SystemCommands::Memory(args) => data.memory(args),
-        SystemCommands::Disk => todo!(),
+        SystemCommands::Cpu(args) => Data::run(&args.stdout, |d| d.cpu(args)),
+        SystemCommands::Cpus(args) => Data::run(&args.stdout, |d| d.cpus(args)),
+        SystemCommands::Memory(args) => Data::run(&args.stdout, |d| d.memory(args)),
+        SystemCommands::Disk(args) => Data::run(&args.stdout, |d| d.disk(args)),
+        SystemCommands::Network(args) => Data::run(&args.stdout, |d| d.network(args)),
+        SystemCommands::Top(args) => Data::run(&args.stdout, |d| d.top(args)),
+        SystemCommands::Kill(args) => Data::new(false).kill(args),
+        SystemCommands::Info(args) => Data::run(&args.stdout, |d| d.info(args)),
     }
 }
 
+/// Keeps sysinfo state alive between ticks of follow mode.
+///
+/// Only the parts a command reads are refreshed.
 struct Data {
     sys: System,
+    networks: Option<Networks>,
+    disks: Option<Disks>,
+    follow: bool,
+    /// cpu usage needs a previous refresh
+    cpu_ready: bool,
+    processes_ready: bool,
+    /// last network counters in follow mode
+    net_prev: Option<Counters>,
+    histories: HashMap<String, History>,
+    levels: HashMap<String, Level>,
 }
 
 impl Data {
-    fn new() -> Self {
-        let mut sys = System::new_all();
-        sys.refresh_all();
+    fn new(follow: bool) -> Self {
+        Data {
+            sys: System::new(),
+            networks: None,
+            disks: None,
+            follow,
+            cpu_ready: false,
+            processes_ready: false,
+            net_prev: None,
+            histories: HashMap::new(),
+            levels: HashMap::new(),
+        }
+    }
+
+    /// Print once, or every interval in follow mode.
+    fn run(stdout: &StdoutArgs, mut f: impl FnMut(&mut Self) -> Result<()>) -> Result<()> {
+        let mut data = Data::new(stdout.follow);
+
+        if !stdout.follow {
+            return f(&mut data);
+        }
+
+        loop {
+            f(&mut data)?;
+            io::stdout().flush()?;
+            thread::sleep(stdout.interval);
+        }
+    }
+
+    fn refresh_cpu(&mut self) {
+        self.sys.refresh_cpu_usage();
+
+        if !self.cpu_ready {
+            thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            self.sys.refresh_cpu_usage();
+            self.cpu_ready = true;
+        }
+    }
+
+    fn refresh_processes(&mut self, cpu: bool) {
+        let kind = ProcessRefreshKind::new()
+            .with_memory()
+            .with_cpu()
+            .with_cmd(UpdateKind::OnlyIfNotSet)
+            .with_user(UpdateKind::OnlyIfNotSet);
+
+        self.sys.refresh_processes_specifics(kind);
+
+        if cpu && !self.processes_ready {
+            thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            self.sys.refresh_processes_specifics(kind);
+            self.processes_ready = true;
+        }
+    }
+
+    /// History of `name`, from the state file or in memory in follow mode.
+    fn history(&mut self, name: &str, args: &HistoryArgs) -> Option<&mut History> {
+        if args.history == 0 {
+            return None;
+        }
 
-        Data { sys }
+        let follow = self.follow;
+        let history = self
+            .histories
+            .entry(name.to_owned())
+            .or_insert_with(|| match follow {
+                true => History::memory(args.history),
+                false => History::load(name, args.history),
+            });
+
+        Some(history)
     }
 
-    fn cpu(&mut self) -> Result<()> {
-        self.sys.refresh_cpu();
+    fn cpu(&mut self, args: &SysCpuArgs) -> Result<()> {
+        self.refresh_cpu();
 
         let cpu = self.sys.global_cpu_info();
         let cpu = cpu.cpu_usage();
-        println!("{:#?}", cpu);
+
+        let mut reading = Reading {
+            class: "wayinfo-cpu",
+            icon: "󰻠",
+            value: cpu as f64,
+            max: Some(100.0),
+            unit: |v| format!("{v:.0}%"),
+            text: format!("{:.0}%", cpu),
+            tooltip: vec![format!("cpu: {:.1}%", cpu)],
+            percentage: Some(cpu.round() as usize),
+            json: json!({ "usage": cpu }),
+            level: None,
+        };
+
+        self.check("cpu", &mut reading, &args.threshold, false);
+        reading.print(args.stdout.output, self.history("cpu", &args.history));
+
+        Ok(())
+    }
+
+    fn cpus(&mut self, args: &SysCpusArgs) -> Result<()> {
+        self.refresh_cpu();
+
+        let usages: Vec<f32> = self.sys.cpus().iter().map(|c| c.cpu_usage()).collect();
+        let global = self.sys.global_cpu_info().cpu_usage();
+
+        let text = match args.stdout.output {
+            OutputFormat::Text => usages
+                .iter()
+                .enumerate()
+                .map(|(i, u)| format!("cpu{i} {u:.0}%"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => sparkline(usages.iter().map(|&u| u as f64), 0.0, 100.0),
+        };
+
+        let mut reading = Reading {
+            class: "wayinfo-cpus",
+            icon: "󰻠",
+            value: global as f64,
+            max: Some(100.0),
+            unit: |v| format!("{v:.0}%"),
+            text,
+            tooltip: usages
+                .iter()
+                .enumerate()
+                .map(|(i, u)| format!("cpu{i}: {u:.1}%"))
+                .collect(),
+            percentage: Some(global.round() as usize),
+            json: json!({ "usage": global, "cpus": usages }),
+            level: None,
+        };
+
+        self.check("cpus", &mut reading, &args.threshold, false);
+        reading.print(args.stdout.output, None);
 
         Ok(())
     }
 
     fn memory(&mut self, args: &SysMemArgs) -> Result<()> {
-        let mut count = self.sys.total_memory();
+        self.sys.refresh_memory();
+
+        let total = self.sys.total_memory();
+        let used = self.sys.used_memory();
+        let percent = match total {
+            0 => 0.0,
+            _ => used as f64 / total as f64 * 100.0,
+        };
+
+        let mut count = total;
 
         if args.usage {
-            count = self.sys.used_memory();
+            count = used;
         }
 
-        println!("{}", convert_bytes_to_unit(count));
+        let text = match args.stdout.output {
+            OutputFormat::Text => convert_bytes_to_unit(count),
+            _ => format!("{:.0}%", percent),
+        };
+
+        let mut reading = Reading {
+            class: "wayinfo-memory",
+            icon: "󰍛",
+            value: percent,
+            max: Some(100.0),
+            unit: |v| format!("{v:.0}%"),
+            text,
+            tooltip: vec![format!(
+                "memory: {} / {}",
+                convert_bytes_to_unit(used),
+                convert_bytes_to_unit(total)
+            )],
+            percentage: Some(percent.round() as usize),
+            json: json!({ "total": total, "used": used, "percent": percent }),
+            level: None,
+        };
+
+        self.check("memory", &mut reading, &args.threshold, false);
+        reading.print(args.stdout.output, self.history("memory", &args.history));
 
         Ok(())
     }
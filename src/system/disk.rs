@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use serde_json::json;
+use sysinfo::Disks;
+
+use super::{
+    convert_bytes_to_unit, history::HistoryArgs, threshold::ThresholdArgs, Data, OutputFormat,
+    Reading, StdoutArgs,
+};
+use crate::{errors::Error, Result};
+
+#[derive(Args)]
+pub struct SysDiskArgs {
+    /// mount point
+    #[arg(short, long, default_value = "/")]
+    pub mount: PathBuf,
+
+    /// report free space, thresholds become lower limits
+    #[arg(long)]
+    pub free: bool,
+
+    #[command(flatten)]
+    pub stdout: StdoutArgs,
+
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    #[command(flatten)]
+    pub threshold: ThresholdArgs,
+}
+
+impl Data {
+    pub(super) fn disk(&mut self, args: &SysDiskArgs) -> Result<()> {
+        let disks = self
+            .disks
+            .get_or_insert_with(Disks::new_with_refreshed_list);
+        disks.refresh();
+
+        let Some(disk) = disks.iter().find(|d| d.mount_point() == args.mount) else {
+            return Error::System {
+                msg: format!("mount point not found: {}", args.mount.display()),
+            }
+            .into();
+        };
+
+        let total = disk.total_space();
+        let available = disk.available_space();
+        let used = total.saturating_sub(available);
+        let percent = match total {
+            0 => 0.0,
+            _ => used as f64 / total as f64 * 100.0,
+        };
+        let value = match args.free {
+            true => 100.0 - percent,
+            false => percent,
+        };
+
+        let text = match (args.stdout.output, args.free) {
+            (OutputFormat::Text, true) => convert_bytes_to_unit(available),
+            (OutputFormat::Text, false) => format!(
+                "{} / {}",
+                convert_bytes_to_unit(used),
+                convert_bytes_to_unit(total)
+            ),
+            _ => format!("{:.0}%", value),
+        };
+
+        let mut reading = Reading {
+            class: "wayinfo-disk",
+            icon: "󰋊",
+            value,
+            max: Some(100.0),
+            unit: |v| format!("{v:.0}%"),
+            text,
+            tooltip: vec![
+                format!(
+                    "{} ({})",
+                    disk.mount_point().display(),
+                    disk.name().to_string_lossy()
+                ),
+                format!("used: {}", convert_bytes_to_unit(used)),
+                format!("free: {}", convert_bytes_to_unit(available)),
+                format!("total: {}", convert_bytes_to_unit(total)),
+            ],
+            percentage: Some(value.round() as usize),
+            json: json!({
+                "mount": disk.mount_point(),
+                "total": total,
+                "available": available,
+                "used": used,
+                "percent": percent,
+            }),
+            level: None,
+        };
+
+        let kind = if args.free { "free" } else { "used" };
+        let name = format!("disk-{kind}-{}", args.mount.display()).replace('/', "_");
+        self.check(&name, &mut reading, &args.threshold, args.free);
+        reading.print(args.stdout.output, self.history(&name, &args.history));
+
+        Ok(())
+    }
+}
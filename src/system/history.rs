@@ -0,0 +1,167 @@
+use std::{
+    collections::VecDeque,
+    env, fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// keep the last N samples and render a sparkline, 0 is off
+    #[arg(long, default_value_t = 0)]
+    pub history: usize,
+}
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Raw counters of the last sample, used to compute rates between invocations
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(super) struct Counters {
+    pub rx: u64,
+    pub tx: u64,
+    /// unix timestamp in milliseconds
+    pub time: u64,
+}
+
+/// Rolling window of samples.
+///
+/// Persisted under `$XDG_RUNTIME_DIR/wayinfo` between invocations.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct History {
+    samples: VecDeque<f64>,
+    #[serde(default)]
+    pub counters: Option<Counters>,
+
+    #[serde(skip)]
+    len: usize,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Load the state file of `name`, starts empty if missing or broken.
+    pub fn load(name: &str, len: usize) -> Self {
+        let path = state_dir().join(format!("{name}.json"));
+
+        let mut history: History = fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        history.len = len;
+        history.path = Some(path);
+        history.trim();
+        history
+    }
+
+    /// Kept in memory only, for follow mode.
+    pub fn memory(len: usize) -> Self {
+        History {
+            len,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.samples.push_back(value);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.samples.len() > self.len {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Write back the state file, failures only logged.
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let re = serde_json::to_string(self).unwrap();
+        let saved = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, re));
+
+        if let Err(e) = saved {
+            log::warn!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+
+    /// `max` fixes the top of the scale, such as 100 for percents,
+    /// otherwise it follows the window.
+    pub fn sparkline(&self, max: Option<f64>) -> String {
+        let (low, high) = match max {
+            Some(max) => (0.0, max),
+            None => match self.stats() {
+                Some((min, _, max)) => (min, max),
+                None => return String::new(),
+            },
+        };
+
+        sparkline(self.samples.iter().copied(), low, high)
+    }
+
+    /// min, avg, max
+    pub fn stats(&self) -> Option<(f64, f64, f64)> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let min = self.samples.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self
+            .samples
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let avg = self.samples.iter().sum::<f64>() / self.samples.len() as f64;
+
+        Some((min, avg, max))
+    }
+
+    /// Tooltip line with min/avg/max formatted by `unit`
+    pub fn summary(&self, unit: impl Fn(f64) -> String) -> Option<String> {
+        self.stats().map(|(min, avg, max)| {
+            format!(
+                "min {} avg {} max {} ({} samples)",
+                unit(min),
+                unit(avg),
+                unit(max),
+                self.samples.len()
+            )
+        })
+    }
+}
+
+/// Render values between `low` and `high` as block characters
+pub(super) fn sparkline(values: impl Iterator<Item = f64>, low: f64, high: f64) -> String {
+    values
+        .map(|v| {
+            let level = if high > low {
+                ((v - low) / (high - low) * (SPARKS.len() - 1) as f64).round()
+            } else {
+                0.0
+            };
+            SPARKS[(level.max(0.0) as usize).min(SPARKS.len() - 1)]
+        })
+        .collect()
+}
+
+pub(super) fn state_dir() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join("wayinfo")
+}
+
+pub(super) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+
+use chrono::{Local, TimeZone};
+use clap::Args;
+use serde::Serialize;
+use sysinfo::{ProcessRefreshKind, System, UpdateKind, User, Users};
+
+use super::{Data, OutputFormat, StdoutArgs};
+use crate::{waybar::WaybarData, Result};
+
+#[derive(Args)]
+pub struct SysInfoArgs {
+    #[command(flatten)]
+    pub stdout: StdoutArgs,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HostInfo {
+    hostname: String,
+    kernel: String,
+    os: String,
+    os_version: String,
+    /// seconds
+    uptime: u64,
+    uptime_text: String,
+    /// unix timestamp
+    boot_time: u64,
+    boot_time_text: String,
+    load: [f64; 3],
+    users: usize,
+}
+
+impl HostInfo {
+    fn lines(&self) -> Vec<String> {
+        vec![
+            format!("hostname: {}", self.hostname),
+            format!("os:       {} {}", self.os, self.os_version),
+            format!("kernel:   {}", self.kernel),
+            format!("uptime:   {}", self.uptime_text),
+            format!("boot:     {}", self.boot_time_text),
+            format!(
+                "load:     {:.2} {:.2} {:.2}",
+                self.load[0], self.load[1], self.load[2]
+            ),
+            format!("users:    {}", self.users),
+        ]
+    }
+}
+
+impl From<HostInfo> for WaybarData {
+    fn from(val: HostInfo) -> Self {
+        WaybarData {
+            class: "wayinfo-info".to_owned(),
+            text: format!("󰔟 {}", val.uptime_text),
+            alt: Some(format!(
+                "{:.2} {:.2} {:.2}",
+                val.load[0], val.load[1], val.load[2]
+            )),
+            tooltip: Some(val.lines().join("\n")),
+            percentage: None,
+        }
+    }
+}
+
+impl Data {
+    fn host_info(&mut self) -> HostInfo {
+        let unknown = || "unknown".to_owned();
+
+        let uptime = System::uptime();
+        let boot_time = System::boot_time();
+        let load = System::load_average();
+
+        let boot_time_text = match Local.timestamp_opt(boot_time as i64, 0).single() {
+            Some(t) => t.format("%Y-%m-%d %H:%M").to_string(),
+            None => unknown(),
+        };
+
+        HostInfo {
+            hostname: System::host_name().unwrap_or_else(unknown),
+            kernel: System::kernel_version().unwrap_or_else(unknown),
+            os: System::name().unwrap_or_else(unknown),
+            os_version: System::os_version().unwrap_or_default(),
+            uptime,
+            uptime_text: format_uptime(uptime),
+            boot_time,
+            boot_time_text,
+            load: [load.one, load.five, load.fifteen],
+            users: self.logged_in_users(),
+        }
+    }
+
+    /// Users with a login session, from logind or utmp.
+    ///
+    /// Without either, owners of session leader processes are counted,
+    /// skipping system accounts and root, which owns the daemon sessions.
+    /// Processes are only loaded for this fallback.
+    fn logged_in_users(&mut self) -> usize {
+        if let Some(names) = login_sessions() {
+            return names.len();
+        }
+
+        let kind = ProcessRefreshKind::new().with_user(UpdateKind::OnlyIfNotSet);
+        self.sys.refresh_processes_specifics(kind);
+        let users = Users::new_with_refreshed_list();
+
+        let names: HashSet<_> = self
+            .sys
+            .processes()
+            .values()
+            .filter(|p| p.session_id() == Some(p.pid()))
+            .filter_map(|p| p.user_id())
+            .filter_map(|uid| users.get_user_by_id(uid))
+            .filter(|u| is_login_user(u))
+            .map(|u| u.name().to_owned())
+            .collect();
+
+        names.len()
+    }
+
+    pub(super) fn info(&mut self, args: &SysInfoArgs) -> Result<()> {
+        let info = self.host_info();
+
+        match args.stdout.output {
+            OutputFormat::Text => println!("{}", info.lines().join("\n")),
+            OutputFormat::Json => {
+                let re = serde_json::to_string(&info).unwrap();
+                println!("{re}");
+            }
+            OutputFormat::Waybar => {
+                let data: WaybarData = info.into();
+                let re = serde_json::to_string(&data).unwrap();
+                println!("{re}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn is_login_user(user: &User) -> bool {
+    (1000..65534).contains(&**user.id())
+}
+
+#[cfg(not(unix))]
+fn is_login_user(_user: &User) -> bool {
+    true
+}
+
+/// Names of users with a session, None when neither logind nor utmp is available.
+#[cfg(target_os = "linux")]
+fn login_sessions() -> Option<HashSet<String>> {
+    use std::fs;
+
+    if let Ok(dir) = fs::read_dir("/run/systemd/sessions") {
+        let names = dir
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !entry.file_name().to_string_lossy().ends_with(".ref"))
+            .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+            .filter_map(|text| logind_user(&text))
+            .collect();
+        return Some(names);
+    }
+
+    let bytes = fs::read("/var/run/utmp")
+        .or_else(|_| fs::read("/run/utmp"))
+        .ok()?;
+    Some(utmp_users(&bytes))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn login_sessions() -> Option<HashSet<String>> {
+    None
+}
+
+/// `USER=` of a logind session file, only user sessions that are not closing
+#[cfg(any(target_os = "linux", test))]
+fn logind_user(text: &str) -> Option<String> {
+    let field = |key: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+    };
+
+    if field("CLASS") != Some("user") || field("STATE") == Some("closing") {
+        return None;
+    }
+    field("USER").map(str::to_owned)
+}
+
+/// glibc `struct utmp`, 384 bytes
+#[cfg(any(target_os = "linux", test))]
+const UTMP_SIZE: usize = 384;
+#[cfg(any(target_os = "linux", test))]
+const UTMP_USER_PROCESS: i16 = 7;
+/// offset of `ut_user[32]`
+#[cfg(any(target_os = "linux", test))]
+const UTMP_USER: usize = 44;
+
+/// Names of `USER_PROCESS` entries
+#[cfg(any(target_os = "linux", test))]
+fn utmp_users(bytes: &[u8]) -> HashSet<String> {
+    bytes
+        .chunks_exact(UTMP_SIZE)
+        .filter(|record| i16::from_ne_bytes([record[0], record[1]]) == UTMP_USER_PROCESS)
+        .map(|record| {
+            let user = &record[UTMP_USER..UTMP_USER + 32];
+            let len = user.iter().position(|&b| b == 0).unwrap_or(user.len());
+            String::from_utf8_lossy(&user[..len]).into_owned()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// 3d 4h, 4h 12m, 12m
+fn format_uptime(secs: u64) -> String {
+    let days = secs / 86400;
+    let hours = secs / 3600 % 24;
+    let minutes = secs / 60 % 60;
+
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logind_sessions() {
+        let session = "# This is private data. Do not parse.\nUID=1000\nUSER=alice\nACTIVE=1\nSTATE=active\nTTY=tty1\nCLASS=user\n";
+        assert_eq!(logind_user(session).as_deref(), Some("alice"));
+
+        let greeter = "UID=967\nUSER=greeter\nSTATE=online\nCLASS=greeter\n";
+        assert_eq!(logind_user(greeter), None);
+
+        let closing = "UID=1001\nUSER=bob\nSTATE=closing\nCLASS=user\n";
+        assert_eq!(logind_user(closing), None);
+    }
+
+    fn utmp_record(kind: i16, user: &str) -> Vec<u8> {
+        let mut record = vec![0u8; UTMP_SIZE];
+        record[..2].copy_from_slice(&kind.to_ne_bytes());
+        record[UTMP_USER..UTMP_USER + user.len()].copy_from_slice(user.as_bytes());
+        record
+    }
+
+    #[test]
+    fn utmp() {
+        // BOOT_TIME, LOGIN_PROCESS, two sessions of alice, root on a tty
+        let bytes = [
+            utmp_record(2, "reboot"),
+            utmp_record(6, "LOGIN"),
+            utmp_record(UTMP_USER_PROCESS, "alice"),
+            utmp_record(UTMP_USER_PROCESS, "alice"),
+            utmp_record(UTMP_USER_PROCESS, "root"),
+        ]
+        .concat();
+
+        let users = utmp_users(&bytes);
+        assert_eq!(users.len(), 2);
+        assert!(users.contains("alice") && users.contains("root"));
+    }
+
+    #[test]
+    fn uptime() {
+        assert_eq!(format_uptime(59), "0m");
+        assert_eq!(format_uptime(3 * 3600 + 120), "3h 2m");
+        assert_eq!(format_uptime(3 * 86400 + 4 * 3600 + 59), "3d 4h");
+    }
+}
@@ -0,0 +1,126 @@
+use std::{thread, time::Duration};
+
+use clap::Args;
+use serde_json::json;
+use sysinfo::Networks;
+
+use super::{
+    convert_bytes_to_unit,
+    history::{now_millis, Counters, HistoryArgs},
+    threshold::ThresholdArgs,
+    Data, Reading, StdoutArgs,
+};
+use crate::Result;
+
+/// Sample window when there is no previous sample
+const SAMPLE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Args)]
+pub struct SysNetArgs {
+    /// interface name, all interfaces except loopback by default
+    #[arg(short, long)]
+    pub interface: Option<String>,
+
+    #[command(flatten)]
+    pub stdout: StdoutArgs,
+
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    /// thresholds of throughput in bytes per second
+    #[command(flatten)]
+    pub threshold: ThresholdArgs,
+}
+
+fn counters(networks: &Networks, interface: Option<&str>) -> Counters {
+    let (rx, tx) = networks
+        .iter()
+        .filter(|(name, _)| match interface {
+            Some(i) => name.as_str() == i,
+            None => name.as_str() != "lo",
+        })
+        .fold((0, 0), |(rx, tx), (_, data)| {
+            (rx + data.total_received(), tx + data.total_transmitted())
+        });
+
+    Counters {
+        rx,
+        tx,
+        time: now_millis(),
+    }
+}
+
+/// bytes per second between two samples
+fn rate(prev: &Counters, now: &Counters) -> Option<(f64, f64)> {
+    let secs = now.time.checked_sub(prev.time)? as f64 / 1000.0;
+    if secs <= 0.0 {
+        return None;
+    }
+
+    let rx = now.rx.checked_sub(prev.rx)? as f64 / secs;
+    let tx = now.tx.checked_sub(prev.tx)? as f64 / secs;
+    Some((rx, tx))
+}
+
+fn format_rate(bytes: f64) -> String {
+    format!("{}/s", convert_bytes_to_unit(bytes as u64))
+}
+
+impl Data {
+    pub(super) fn network(&mut self, args: &SysNetArgs) -> Result<()> {
+        let interface = args.interface.as_deref();
+        let name = format!("network-{}", interface.unwrap_or("all"));
+
+        let networks = self
+            .networks
+            .get_or_insert_with(Networks::new_with_refreshed_list);
+        networks.refresh();
+        let mut now = counters(networks, interface);
+
+        // last tick, or counters of the last invocation, otherwise sample for a while
+        let prev = self
+            .net_prev
+            .or_else(|| self.history(&name, &args.history).and_then(|h| h.counters));
+        let (rx, tx) = match prev.and_then(|prev| rate(&prev, &now)) {
+            Some(rate) => rate,
+            None => {
+                let prev = now;
+                thread::sleep(SAMPLE_WINDOW);
+                let networks = self
+                    .networks
+                    .get_or_insert_with(Networks::new_with_refreshed_list);
+                networks.refresh();
+                now = counters(networks, interface);
+                rate(&prev, &now).unwrap_or_default()
+            }
+        };
+
+        self.net_prev = Some(now);
+        if let Some(history) = self.history(&name, &args.history) {
+            history.counters = Some(now);
+        }
+
+        let mut reading = Reading {
+            class: "wayinfo-network",
+            icon: "󰛳",
+            value: rx + tx,
+            max: None,
+            unit: format_rate,
+            text: format!("↓{} ↑{}", format_rate(rx), format_rate(tx)),
+            tooltip: vec![
+                format!("interface: {}", interface.unwrap_or("all")),
+                format!("download: {}", format_rate(rx)),
+                format!("upload: {}", format_rate(tx)),
+            ],
+            percentage: None,
+            json: json!({ "interface": interface, "rx": rx, "tx": tx }),
+            level: None,
+        };
+
+        self.check(&name, &mut reading, &args.threshold, false);
+
+        reading.print(args.stdout.output, self.history(&name, &args.history));
+
+        Ok(())
+    }
+}
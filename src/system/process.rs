@@ -0,0 +1,180 @@
+use std::cmp::Reverse;
+
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use sysinfo::Pid;
+
+use super::{convert_bytes_to_unit, Data, OutputFormat, StdoutArgs};
+use crate::{errors::Error, waybar::WaybarData, Result};
+
+#[derive(Args)]
+pub struct SysTopArgs {
+    /// sort processes by
+    #[arg(short, long, value_enum, default_value_t = TopSort::Cpu)]
+    pub sort: TopSort,
+
+    /// number of processes
+    #[arg(short = 'n', long, default_value_t = 5)]
+    pub limit: usize,
+
+    #[command(flatten)]
+    pub stdout: StdoutArgs,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum TopSort {
+    /// cpu usage
+    Cpu,
+    /// resident memory
+    Mem,
+}
+
+#[derive(Args)]
+pub struct SysKillArgs {
+    /// pid or exact process name
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProcessInfo {
+    name: String,
+    pid: u32,
+    cpu: f32,
+    rss: u64,
+    command: String,
+}
+
+impl ProcessInfo {
+    fn row(&self) -> String {
+        // keep the table on one line per process
+        let command: String = self
+            .command
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(COMMAND_WIDTH)
+            .collect();
+
+        format!(
+            "{:>7} {:>6.1} {:>10}  {:<16} {}",
+            self.pid,
+            self.cpu,
+            convert_bytes_to_unit(self.rss),
+            self.name,
+            command
+        )
+    }
+}
+
+const COMMAND_WIDTH: usize = 60;
+
+fn table_header() -> String {
+    format!(
+        "{:>7} {:>6} {:>10}  {:<16} {}",
+        "PID", "CPU%", "RSS", "NAME", "COMMAND"
+    )
+}
+
+impl Data {
+    fn processes(&mut self, args: &SysTopArgs) -> Vec<ProcessInfo> {
+        self.refresh_processes(true);
+
+        let mut list: Vec<ProcessInfo> = self
+            .sys
+            .processes()
+            .values()
+            // skip threads, only list processes
+            .filter(|p| p.thread_kind().is_none())
+            .map(|p| ProcessInfo {
+                name: p.name().to_owned(),
+                pid: p.pid().as_u32(),
+                cpu: p.cpu_usage(),
+                rss: p.memory(),
+                command: p.cmd().join(" "),
+            })
+            .collect();
+
+        match args.sort {
+            TopSort::Cpu => list.sort_by(|a, b| b.cpu.total_cmp(&a.cpu)),
+            TopSort::Mem => list.sort_by_key(|p| Reverse(p.rss)),
+        }
+
+        list.truncate(args.limit);
+        list
+    }
+
+    pub(super) fn top(&mut self, args: &SysTopArgs) -> Result<()> {
+        let list = self.processes(args);
+
+        match args.stdout.output {
+            OutputFormat::Text => {
+                println!("{}", table_header());
+                for p in &list {
+                    println!("{}", p.row());
+                }
+            }
+            OutputFormat::Json => {
+                let re = serde_json::to_string(&list).unwrap();
+                println!("{re}");
+            }
+            OutputFormat::Waybar => {
+                let text = match list.first() {
+                    Some(p) => match args.sort {
+                        TopSort::Cpu => format!("{} {:.1}%", p.name, p.cpu),
+                        TopSort::Mem => format!("{} {}", p.name, convert_bytes_to_unit(p.rss)),
+                    },
+                    None => String::new(),
+                };
+
+                let mut tooltip = vec![table_header()];
+                tooltip.extend(list.iter().map(ProcessInfo::row));
+
+                let data = WaybarData {
+                    class: "wayinfo-top".to_owned(),
+                    text,
+                    alt: None,
+                    tooltip: Some(tooltip.join("\n")),
+                    percentage: None,
+                };
+                let re = serde_json::to_string(&data).unwrap();
+                println!("{re}");
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn kill(&mut self, args: &SysKillArgs) -> Result<()> {
+        self.refresh_processes(false);
+
+        let pids: Vec<Pid> = match args.target.parse::<usize>() {
+            Ok(pid) => vec![Pid::from(pid)],
+            Err(_) => self
+                .sys
+                .processes_by_exact_name(&args.target)
+                .map(|p| p.pid())
+                .collect(),
+        };
+
+        let killed: Vec<u32> = pids
+            .into_iter()
+            .filter_map(|pid| self.sys.process(pid))
+            .filter(|p| p.kill())
+            .map(|p| p.pid().as_u32())
+            .collect();
+
+        if killed.is_empty() {
+            return Error::System {
+                msg: format!("no process killed: {}", args.target),
+            }
+            .into();
+        }
+
+        for pid in killed {
+            println!("{pid}");
+        }
+
+        Ok(())
+    }
+}
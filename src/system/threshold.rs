@@ -0,0 +1,200 @@
+use std::fs;
+
+use clap::Args;
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+use super::{history::state_dir, Data, Reading};
+
+#[derive(Args)]
+pub struct ThresholdArgs {
+    /// warning threshold, adds waybar class warning
+    #[arg(long)]
+    pub warning: Option<f64>,
+
+    /// critical threshold, adds waybar class critical
+    #[arg(long)]
+    pub critical: Option<f64>,
+
+    /// lower values are worse, such as free space
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// margin to go back below a threshold, avoids flapping
+    #[arg(long, default_value_t = 5.0)]
+    pub hysteresis: f64,
+
+    /// send a desktop notification when a threshold is crossed
+    #[arg(long)]
+    pub notify: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Level {
+    #[default]
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Normal => "normal",
+            Level::Warning => "warning",
+            Level::Critical => "critical",
+        }
+    }
+}
+
+impl ThresholdArgs {
+    fn is_set(&self) -> bool {
+        self.warning.is_some() || self.critical.is_some()
+    }
+
+    /// Level of `value`, moved toward normal by `margin`
+    fn level(&self, value: f64, margin: f64, reverse: bool) -> Level {
+        let over = |limit: Option<f64>| match (limit, reverse) {
+            (Some(limit), false) => value + margin >= limit,
+            (Some(limit), true) => value - margin <= limit,
+            (None, _) => false,
+        };
+
+        if over(self.critical) {
+            Level::Critical
+        } else if over(self.warning) {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+
+    /// Raise at once, only lower after leaving the threshold by the hysteresis.
+    fn classify(&self, value: f64, prev: Level, reverse: bool) -> Level {
+        let raw = self.level(value, 0.0, reverse);
+
+        if raw >= prev {
+            raw
+        } else {
+            self.level(value, self.hysteresis, reverse).min(prev)
+        }
+    }
+}
+
+impl Data {
+    /// Set the state class of `reading`, notify once when it gets worse.
+    pub(super) fn check(
+        &mut self,
+        name: &str,
+        reading: &mut Reading,
+        args: &ThresholdArgs,
+        reverse: bool,
+    ) {
+        if !args.is_set() {
+            return;
+        }
+
+        let reverse = args.reverse || reverse;
+        let path = state_dir().join(format!("{name}.level"));
+
+        let prev = match self.levels.get(name) {
+            Some(level) => *level,
+            None if self.follow => Level::Normal,
+            None => fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+        };
+
+        let level = args.classify(reading.value, prev, reverse);
+        reading.level = Some(level);
+
+        if args.notify && level > prev {
+            notify(name, level, &reading.text);
+        }
+
+        self.levels.insert(name.to_owned(), level);
+        if !self.follow && level != prev {
+            let saved = fs::create_dir_all(state_dir())
+                .and_then(|_| fs::write(&path, serde_json::to_string(&level).unwrap()));
+            if let Err(e) = saved {
+                log::warn!("Failed to save {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn notify(name: &str, level: Level, text: &str) {
+    let mut notification = Notification::new();
+    notification
+        .appname("wayinfo")
+        .summary(&format!("{name} {}", level.as_str()))
+        .body(text);
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if level == Level::Critical {
+        notification.urgency(notify_rust::Urgency::Critical);
+    }
+
+    if let Err(e) = notification.show() {
+        log::warn!("Failed to send notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(warning: f64, critical: f64) -> ThresholdArgs {
+        ThresholdArgs {
+            warning: Some(warning),
+            critical: Some(critical),
+            reverse: false,
+            hysteresis: 5.0,
+            notify: false,
+        }
+    }
+
+    #[test]
+    fn classify_raises_at_once() {
+        let args = args(70.0, 90.0);
+        assert_eq!(args.classify(50.0, Level::Normal, false), Level::Normal);
+        assert_eq!(args.classify(70.0, Level::Normal, false), Level::Warning);
+        assert_eq!(args.classify(95.0, Level::Normal, false), Level::Critical);
+    }
+
+    #[test]
+    fn classify_hysteresis() {
+        let args = args(70.0, 90.0);
+        // 在滞后区间内保持
+        assert_eq!(args.classify(88.0, Level::Critical, false), Level::Critical);
+        assert_eq!(args.classify(66.0, Level::Warning, false), Level::Warning);
+        // 离开滞后区间后降低，一次可以降低多级
+        assert_eq!(args.classify(84.0, Level::Critical, false), Level::Warning);
+        assert_eq!(args.classify(64.0, Level::Warning, false), Level::Normal);
+        assert_eq!(args.classify(10.0, Level::Critical, false), Level::Normal);
+    }
+
+    #[test]
+    fn classify_reverse() {
+        // 剩余空间，越低越差
+        let args = args(20.0, 10.0);
+        assert_eq!(args.classify(50.0, Level::Normal, true), Level::Normal);
+        assert_eq!(args.classify(20.0, Level::Normal, true), Level::Warning);
+        assert_eq!(args.classify(5.0, Level::Normal, true), Level::Critical);
+        assert_eq!(args.classify(12.0, Level::Critical, true), Level::Critical);
+        assert_eq!(args.classify(16.0, Level::Critical, true), Level::Warning);
+        assert_eq!(args.classify(24.0, Level::Warning, true), Level::Warning);
+        assert_eq!(args.classify(26.0, Level::Warning, true), Level::Normal);
+    }
+
+    #[test]
+    fn classify_warning_only() {
+        let args = ThresholdArgs {
+            critical: None,
+            ..args(70.0, 0.0)
+        };
+        assert_eq!(args.classify(100.0, Level::Normal, false), Level::Warning);
+    }
+}
@@ -1,13 +1,35 @@
+mod editor;
+mod session;
+mod zellij;
+
+use std::{env, process::Command};
+
 use clap::{Args, Subcommand};
 
-use crate::{utils::shell_exec, Result};
+use crate::{errors::Error, Result};
+use editor::{Editor, Location};
 
 #[derive(Subcommand)]
 pub enum TmuxCommands {
-    /// split window and run
+    /// split window and run, in tmux or zellij
     Run(SplitArgs),
-    /// open file with helix editor
-    Hx(OpenArgs),
+    /// open file with helix, neovim, kakoune or vim
+    #[command(visible_alias = "hx")]
+    Open(OpenArgs),
+    /// sessions json with attached clients, windows and activity
+    Sessions,
+    /// create or attach the session of a project, windows from config
+    Project {
+        /// folder of project, name of session
+        dir: String,
+    },
+    /// switch or attach to session
+    Switch {
+        /// name of session
+        name: String,
+    },
+    /// waybar format, number of sessions
+    Waybar,
 }
 
 #[derive(Args)]
@@ -18,15 +40,41 @@ pub struct SplitArgs {
     #[arg(long)]
     pub project: Option<String>,
 
+    #[command(flatten)]
+    pub pane: PaneArgs,
+
     pub commands: Vec<String>,
 }
 
+/// 选择已有的 pane
+#[derive(Args)]
+pub struct PaneArgs {
+    /// 发送到指定的 pane，tmux 为 pane id，如 `%3`，zellij 为 pane 名称，不再查找
+    #[arg(short, long)]
+    pub target: Option<String>,
+
+    /// 可以复用的 pane 当前命令，可以多次指定，
+    /// 默认 run 为 sh, bash, zsh, fish，open 为编辑器命令
+    #[arg(long = "reuse-command", value_name = "COMMAND")]
+    pub reuse_commands: Vec<String>,
+}
+
 /// {project} {file}:{line}:{col}
 #[derive(Args)]
 pub struct OpenArgs {
     #[arg(short, long)]
     pub vertical: bool,
 
+    #[arg(short, long, value_enum, default_value_t = Editor::Helix)]
+    pub editor: Editor,
+
+    /// nvim socket 或 kak session，默认按项目目录名称
+    #[arg(long)]
+    pub server: Option<String>,
+
+    #[command(flatten)]
+    pub pane: PaneArgs,
+
     /// {project} folder of project
     pub project: String,
     /// {file}:{line}:{col}
@@ -37,78 +85,250 @@ pub struct OpenArgs {
 pub fn parse(cmd: &TmuxCommands) -> Result<()> {
     match cmd {
         TmuxCommands::Run(args) => split_run(args),
-        TmuxCommands::Hx(args) => hx_open(args),
+        TmuxCommands::Open(args) => open(args),
+        TmuxCommands::Sessions => session::print_sessions(),
+        TmuxCommands::Project { dir } => session::project(dir),
+        TmuxCommands::Switch { name } => session::switch(name),
+        TmuxCommands::Waybar => session::waybar(),
     }
 }
 
+/// wayinfo 创建 pane 时设置的 user option，值为用途
+const ROLE_OPTION: &str = "@wayinfo";
+const ROLE_RUN: &str = "run";
+
+const RUN_COMMANDS: [&str; 4] = ["sh", "bash", "zsh", "fish"];
+
 fn split_run(args: &SplitArgs) -> Result<()> {
+    let mux = multiplexer();
     let script = args.commands.join(" ");
 
-    let split = match &args.vertical {
-        true => "-v",
-        false => "-h",
+    let target = match find_pane(mux.as_ref(), &args.pane, ROLE_RUN, &RUN_COMMANDS)? {
+        Some(target) => target,
+        None => mux.split(args.vertical, args.project.as_deref(), ROLE_RUN, &[])?,
     };
 
-    let project = match &args.project {
-        Some(p) => format!("-c {p}"),
-        None => "".to_owned(),
-    };
+    mux.send_line(&target, &script)
+}
+
+/// 编辑器 socket 或 session 存在时直接打开，否则复用或新建 pane
+fn open(args: &OpenArgs) -> Result<()> {
+    let editor = args.editor;
+    let loc = Location::parse(&args.project, &args.file);
+    let server = editor.server(&args.project, args.server.as_deref());
+
+    match editor.remote(server.as_deref(), &loc) {
+        Ok(true) => return Ok(()),
+        Ok(false) => {}
+        Err(err) => log::warn!("Failed to open with editor server: {}", err),
+    }
+
+    let mux = multiplexer();
+    match find_pane(mux.as_ref(), &args.pane, editor.role(), editor.commands())? {
+        Some(target) => mux.send_inputs(&target, &editor.inputs(&loc)),
+        None => {
+            let argv = editor.argv(&args.project, server.as_deref(), &loc);
+            let command: Vec<&str> = argv.iter().map(String::as_str).collect();
+            mux.split(args.vertical, Some(&args.project), editor.role(), &command)?;
+            Ok(())
+        }
+    }
+}
+
+/// 终端复用器中的 pane 操作
+trait Multiplexer {
+    /// 当前窗口中的 pane，不包括 wayinfo 所在的 pane
+    fn panes(&self) -> Result<Vec<Pane>>;
 
-    let cmd = format!("tmux split-window {split} {project} & tmux send '{script}' Enter");
+    /// 新的 pane，设置用途，返回 pane id。`command` 不为空时直接执行，不经过 shell
+    fn split(
+        &self,
+        vertical: bool,
+        dir: Option<&str>,
+        role: &str,
+        command: &[&str],
+    ) -> Result<String>;
 
-    let cmd = tmux_pane(cmd, script, &["sh", "tmux"])?;
-    let _output = shell_exec(&cmd)?;
-    // println!("{cmd} \n {output}");
+    fn send_inputs(&self, target: &str, inputs: &[Input]) -> Result<()>;
+
+    /// 输入文本后回车
+    fn send_line(&self, target: &str, text: &str) -> Result<()> {
+        self.send_inputs(target, &[Input::Text(text.to_owned()), Input::Key("Enter")])
+    }
+}
 
-    Ok(())
+/// 在 zellij 中时使用 zellij，否则为 tmux。两者嵌套时优先 tmux
+fn multiplexer() -> Box<dyn Multiplexer> {
+    if env::var_os("TMUX").is_none() && env::var_os("ZELLIJ").is_some() {
+        return Box::new(zellij::Zellij);
+    }
+    Box::new(Tmux)
 }
 
-fn hx_open(args: &OpenArgs) -> Result<()> {
-    let project = args.project.clone();
-    let file = args.file.clone();
+/// tmux，当前窗口为 `$TMUX_PANE` 所在的窗口
+struct Tmux;
+
+impl Multiplexer for Tmux {
+    fn panes(&self) -> Result<Vec<Pane>> {
+        let current = env::var("TMUX_PANE").ok();
+        let panes = list_panes(current.as_deref())?
+            .into_iter()
+            .filter(|p| Some(&p.id) != current.as_ref())
+            .collect();
+        Ok(panes)
+    }
 
-    let split = match &args.vertical {
+    fn split(
+        &self,
+        vertical: bool,
+        dir: Option<&str>,
+        role: &str,
+        command: &[&str],
+    ) -> Result<String> {
+        split_window(vertical, dir, role, command)
+    }
+
+    fn send_inputs(&self, target: &str, inputs: &[Input]) -> Result<()> {
+        for input in inputs {
+            match input {
+                Input::Text(text) => tmux(&["send-keys", "-t", target, "-l", "--", text])?,
+                Input::Key(key) => tmux(&["send-keys", "-t", target, key])?,
+            };
+        }
+        Ok(())
+    }
+}
+
+fn split_window(vertical: bool, dir: Option<&str>, role: &str, command: &[&str]) -> Result<String> {
+    let split = match vertical {
         true => "-v",
         false => "-h",
     };
 
-    let cmd =
-        format!("tmux split-window {split} {project} & tmux send 'hx -w {project} {file} ' Enter");
+    let current = env::var("TMUX_PANE").ok();
+    let mut argv = vec!["split-window", split, "-P", "-F", "#{pane_id}"];
+    if let Some(current) = &current {
+        argv.extend(["-t", current]);
+    }
+    if let Some(dir) = dir {
+        argv.extend(["-c", dir]);
+    }
+    if !command.is_empty() {
+        argv.push("--");
+        argv.extend(command);
+    }
 
-    let script = format!(":o {file}");
+    let id = tmux(&argv)?.trim().to_owned();
+    tmux(&["set-option", "-p", "-t", &id, ROLE_OPTION, role])?;
 
-    let cmd = tmux_pane(cmd, script, &["hx"])?;
-    let _output = shell_exec(&cmd)?;
-    // println!("{cmd} \n {output}");
+    Ok(id)
+}
 
-    Ok(())
+/// 输入到 pane 的文本或按键
+pub enum Input {
+    /// 按字面输入，引号和按键名不会被解释
+    Text(String),
+    /// tmux 按键名，如 `Enter`, `Escape`
+    Key(&'static str),
 }
 
-fn tmux_pane(cmd: String, script: String, ends: &[&str]) -> Result<String> {
-    let has_pane =
-        shell_exec("tmux list-panes -F \"#{window_index} #{pane_index} #{pane_current_command}\"")?;
+#[derive(Debug, PartialEq)]
+struct Pane {
+    id: String,
+    command: String,
+    role: String,
+}
 
-    let cmd = if let Some(has) = has_pane
-        .lines()
-        .filter(
-            |&s| ends.iter().any(|&b| s.ends_with(b)), // s.ends_with("tmux") || s.ends_with("sh")
-        )
-        .last()
-    {
-        let parts: Vec<&str> = has.split_whitespace().collect();
+/// 当前窗口中可以复用的 pane id，如 tmux 的 `%3`，zellij 的 pane 名称。
+/// 优先 wayinfo 创建的同用途 pane，当前命令需要完全相同，不包括 wayinfo 所在的 pane
+fn find_pane(
+    mux: &dyn Multiplexer,
+    args: &PaneArgs,
+    role: &str,
+    commands: &[&str],
+) -> Result<Option<String>> {
+    if let Some(target) = &args.target {
+        return Ok(Some(target.clone()));
+    }
 
-        if parts.len() == 3 {
-            let window_id = parts[0];
-            let pane_id = parts[1];
-            // let name = parts[2];
-            let cmd = format!("tmux send -t {window_id}.{pane_id} '{script}' Enter");
-            cmd
-        } else {
-            cmd
-        }
-    } else {
-        cmd
+    let reuse: Vec<&str> = match args.reuse_commands.is_empty() {
+        true => commands.to_vec(),
+        false => args.reuse_commands.iter().map(String::as_str).collect(),
     };
 
-    Ok(cmd)
+    let panes: Vec<Pane> = mux
+        .panes()?
+        .into_iter()
+        .filter(|p| reuse.contains(&p.command.as_str()))
+        .collect();
+
+    let pane = panes
+        .iter()
+        .find(|p| p.role == role)
+        .or_else(|| panes.first());
+
+    Ok(pane.map(|p| p.id.clone()))
+}
+
+/// `target` 所在窗口的 pane，没有时为当前窗口
+fn list_panes(target: Option<&str>) -> Result<Vec<Pane>> {
+    // 与 list-sessions 相同用空格分隔，用途可以有空格，放在最后
+    let format = format!("#{{pane_id}} #{{pane_current_command}} #{{{ROLE_OPTION}}}");
+    let mut argv = vec!["list-panes", "-F", &format];
+    if let Some(target) = target {
+        argv.extend(["-t", target]);
+    }
+
+    Ok(parse_panes(&tmux(&argv)?))
+}
+
+fn parse_panes(text: &str) -> Vec<Pane> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ' ');
+            Some(Pane {
+                id: parts.next()?.to_owned(),
+                command: parts.next()?.to_owned(),
+                role: parts.next().unwrap_or_default().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// 不经过 shell 执行 tmux，失败时返回退出状态和 stderr
+fn tmux(args: &[&str]) -> Result<String> {
+    let output = Command::new("tmux").args(args).output()?;
+
+    if !output.status.success() {
+        return Error::Tmux {
+            status: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        }
+        .into();
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panes() {
+        let pane = |id: &str, command: &str, role: &str| Pane {
+            id: id.to_owned(),
+            command: command.to_owned(),
+            role: role.to_owned(),
+        };
+        assert_eq!(
+            parse_panes("%0 zsh \n%3 hx editor\n%4 bash dev server\n%5 fish"),
+            [
+                pane("%0", "zsh", ""),
+                pane("%3", "hx", "editor"),
+                pane("%4", "bash", "dev server"),
+                pane("%5", "fish", ""),
+            ]
+        );
+    }
 }
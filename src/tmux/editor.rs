@@ -0,0 +1,450 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+
+use super::Input;
+use crate::{errors::Error, Result};
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Editor {
+    Helix,
+    Nvim,
+    Kak,
+    Vim,
+}
+
+/// `{file}:{line}:{col}`，行列从 1 开始
+pub struct Location {
+    /// 相对路径基于项目目录
+    pub file: PathBuf,
+    pub line: Option<u32>,
+    pub col: Option<u32>,
+}
+
+impl Location {
+    /// 从右边解析数字，文件名中可以有 `:`
+    pub fn parse(project: &str, text: &str) -> Location {
+        let mut file = text;
+        let mut numbers = Vec::new();
+        while numbers.len() < 2 {
+            match file.rsplit_once(':') {
+                Some((rest, n)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => {
+                    numbers.push(n.parse().ok());
+                    file = rest;
+                }
+                _ => break,
+            }
+        }
+        numbers.reverse();
+
+        // 行号无效时列也无效
+        let line = numbers.first().copied().flatten();
+        Location {
+            file: Path::new(project).join(file),
+            line,
+            col: line.and(numbers.get(1).copied().flatten()),
+        }
+    }
+
+    fn file(&self) -> String {
+        self.file.to_string_lossy().into_owned()
+    }
+
+    /// vim `:call cursor()`
+    fn cursor(&self) -> Option<String> {
+        self.line
+            .map(|line| format!("call cursor({line}, {})", self.col.unwrap_or(1)))
+    }
+}
+
+impl Editor {
+    /// pane 的用途，helix 与之前的 `hx` 一致
+    pub fn role(&self) -> &'static str {
+        match self {
+            Editor::Helix => "hx",
+            Editor::Nvim => "nvim",
+            Editor::Kak => "kak",
+            Editor::Vim => "vim",
+        }
+    }
+
+    /// 可以复用的 pane 当前命令
+    pub fn commands(&self) -> &'static [&'static str] {
+        match self {
+            Editor::Helix => &["hx", "helix"],
+            Editor::Nvim => &["nvim"],
+            Editor::Kak => &["kak"],
+            Editor::Vim => &["vim"],
+        }
+    }
+
+    /// nvim socket 或 kak session，默认按项目目录名称区分
+    pub fn server(&self, project: &str, server: Option<&str>) -> Option<String> {
+        if let Some(server) = server {
+            return Some(server.to_owned());
+        }
+
+        let name: String = Path::new(project)
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+
+        match self {
+            Editor::Nvim => {
+                let dir = env::var_os("XDG_RUNTIME_DIR")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(env::temp_dir);
+                let path = dir.join(format!("wayinfo-nvim-{name}.sock"));
+                Some(path.to_string_lossy().into_owned())
+            }
+            Editor::Kak => Some(format!("wayinfo-{name}")),
+            Editor::Helix | Editor::Vim => None,
+        }
+    }
+
+    /// 新 pane 中执行的命令，kak session 已经存在时作为客户端连接
+    pub fn argv(&self, project: &str, server: Option<&str>, loc: &Location) -> Vec<String> {
+        let file = loc.file();
+        let mut argv: Vec<String> = match self {
+            Editor::Helix => {
+                let file = match (loc.line, loc.col) {
+                    (Some(line), Some(col)) => format!("{file}:{line}:{col}"),
+                    (Some(line), None) => format!("{file}:{line}"),
+                    _ => file,
+                };
+                return vec!["hx".into(), "-w".into(), project.into(), file];
+            }
+            Editor::Kak => {
+                let mut argv = vec!["kak".to_owned()];
+                if let Some(session) = server {
+                    let flag = match kak_sessions().iter().any(|s| s == session) {
+                        true => "-c",
+                        false => "-s",
+                    };
+                    argv.extend([flag.to_owned(), session.to_owned()]);
+                }
+                argv.push(file);
+                if let Some(line) = loc.line {
+                    argv.push(format!("+{line}:{}", loc.col.unwrap_or(1)));
+                }
+                return argv;
+            }
+            Editor::Nvim => match server {
+                Some(socket) => vec!["nvim".into(), "--listen".into(), socket.into()],
+                None => vec!["nvim".into()],
+            },
+            Editor::Vim => vec!["vim".into()],
+        };
+
+        if let Some(cursor) = loc.cursor() {
+            argv.push(format!("+{cursor}"));
+        }
+        argv.push(file);
+        argv
+    }
+
+    /// 在已经打开编辑器的 pane 中输入的按键
+    pub fn inputs(&self, loc: &Location) -> Vec<Input> {
+        let file = loc.file();
+        let mut inputs = vec![Input::Key("Escape")];
+
+        match self {
+            Editor::Helix => {
+                inputs.extend([
+                    Input::Text(format!(":o {}", helix_quote(&file))),
+                    Input::Key("Enter"),
+                ]);
+                if let Some(line) = loc.line {
+                    inputs.extend([Input::Text(format!(":goto {line}")), Input::Key("Enter")]);
+                    inputs.push(Input::Text("gh".to_owned()));
+                    if let Some(col) = loc.col.filter(|&c| c > 1) {
+                        inputs.push(Input::Text(format!("{}l", col - 1)));
+                    }
+                }
+            }
+            Editor::Kak => {
+                inputs.extend([
+                    Input::Text(format!(":{}", kak_edit(loc))),
+                    Input::Key("Enter"),
+                ]);
+            }
+            Editor::Nvim | Editor::Vim => {
+                inputs.extend([
+                    Input::Text(format!(":edit {}", vim_escape(&file))),
+                    Input::Key("Enter"),
+                ]);
+                if let Some(cursor) = loc.cursor() {
+                    inputs.extend([Input::Text(format!(":{cursor}")), Input::Key("Enter")]);
+                }
+            }
+        }
+
+        inputs
+    }
+
+    /// nvim socket 或 kak session 存在时直接发送，不需要 pane，
+    /// 返回是否已经打开。kak session 没有客户端时返回 false
+    pub fn remote(&self, server: Option<&str>, loc: &Location) -> Result<bool> {
+        let Some(server) = server else {
+            return Ok(false);
+        };
+
+        match self {
+            Editor::Nvim if Path::new(server).exists() => {
+                run("nvim", &["--server", server, "--remote", &loc.file()], None)?;
+                if let Some(cursor) = loc.cursor() {
+                    let keys = format!("<C-\\><C-N>:{cursor}<CR>");
+                    run("nvim", &["--server", server, "--remote-send", &keys], None)?;
+                }
+                Ok(true)
+            }
+            Editor::Kak if kak_sessions().iter().any(|s| s == server) => {
+                let Some(client) = kak_clients(server)?.into_iter().next() else {
+                    return Ok(false);
+                };
+                let command = format!(
+                    "evaluate-commands -client {client} {}",
+                    kak_quote(&kak_edit(loc))
+                );
+                run("kak", &["-p", server], Some(&command))?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// `edit -existing 'file' line col`
+fn kak_edit(loc: &Location) -> String {
+    let mut command = format!("edit -existing {}", kak_quote(&loc.file()));
+    if let Some(line) = loc.line {
+        command.push_str(&format!(" {line} {}", loc.col.unwrap_or(1)));
+    }
+    command
+}
+
+/// kakoune 单引号字符串，`'` 写为 `''`
+fn kak_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// helix 命令参数，有空白或引号时加上引号
+fn helix_quote(file: &str) -> String {
+    if !file.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+        return file.to_owned();
+    }
+    match file.contains('"') {
+        false => format!("\"{file}\""),
+        true => format!("'{file}'"),
+    }
+}
+
+/// 与 vim `fnameescape()` 相同的转义
+fn vim_escape(file: &str) -> String {
+    let mut escaped = String::new();
+    for c in file.chars() {
+        if " \t\n*?[{`$\\%#'\"|!<".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 没有安装 kakoune 时为空
+fn kak_sessions() -> Vec<String> {
+    match Command::new("kak").arg("-l").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_owned)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// session 的 `%val{client_list}`。`kak -p` 不等待执行，
+/// 由 session 写入临时文件后读取
+fn kak_clients(session: &str) -> Result<Vec<String>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("clients");
+    let path_text = path.to_string_lossy();
+
+    let command = format!(
+        "evaluate-commands %sh{{ printf '%s' \"$kak_client_list\" > '{path_text}.tmp' && mv '{path_text}.tmp' '{path_text}' }}"
+    );
+    run("kak", &["-p", session], Some(&command))?;
+
+    for _ in 0..40 {
+        if let Ok(text) = fs::read_to_string(&path) {
+            return Ok(text.split_whitespace().map(str::to_owned).collect());
+        }
+        thread::sleep(Duration::from_millis(25));
+    }
+
+    Error::Editor {
+        program: "kak".to_owned(),
+        stderr: format!("no reply from session {session}"),
+    }
+    .into()
+}
+
+/// 执行编辑器命令，`stdin` 写入标准输入
+fn run(program: &str, args: &[&str], stdin: Option<&str>) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let (Some(text), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(text.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Error::Editor {
+            program: program.to_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        }
+        .into();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(inputs: &[Input]) -> Vec<String> {
+        inputs
+            .iter()
+            .map(|input| match input {
+                Input::Text(text) => text.clone(),
+                Input::Key(key) => format!("<{key}>"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_location() {
+        let loc = Location::parse("/p", "src/main.rs:10:5");
+        assert_eq!(loc.file, Path::new("/p/src/main.rs"));
+        assert_eq!((loc.line, loc.col), (Some(10), Some(5)));
+
+        let loc = Location::parse("/p", "src/main.rs:10");
+        assert_eq!((loc.line, loc.col), (Some(10), None));
+
+        let loc = Location::parse("/p", "/abs/a.rs");
+        assert_eq!(loc.file, Path::new("/abs/a.rs"));
+        assert_eq!((loc.line, loc.col), (None, None));
+
+        // 文件名中的 `:`
+        let loc = Location::parse("/p", "a:b.rs:3:1");
+        assert_eq!(loc.file, Path::new("/p/a:b.rs"));
+        assert_eq!((loc.line, loc.col), (Some(3), Some(1)));
+
+        let loc = Location::parse("/p", "a.rs:");
+        assert_eq!(loc.file, Path::new("/p/a.rs:"));
+        assert_eq!(loc.line, None);
+
+        // 行号溢出时不使用列
+        let loc = Location::parse("/p", "a.rs:99999999999:1");
+        assert_eq!(loc.file, Path::new("/p/a.rs"));
+        assert_eq!((loc.line, loc.col), (None, None));
+    }
+
+    #[test]
+    fn argv() {
+        let loc = Location::parse("/p", "my file.rs:10:5");
+
+        assert_eq!(
+            Editor::Helix.argv("/p", None, &loc),
+            ["hx", "-w", "/p", "/p/my file.rs:10:5"]
+        );
+        assert_eq!(
+            Editor::Nvim.argv("/p", Some("/run/nvim.sock"), &loc),
+            [
+                "nvim",
+                "--listen",
+                "/run/nvim.sock",
+                "+call cursor(10, 5)",
+                "/p/my file.rs"
+            ]
+        );
+        assert_eq!(
+            Editor::Vim.argv("/p", None, &Location::parse("/p", "a.rs")),
+            ["vim", "/p/a.rs"]
+        );
+        // 不存在的 session
+        assert_eq!(
+            Editor::Kak.argv("/p", Some("wayinfo-test-no-such-session"), &loc),
+            [
+                "kak",
+                "-s",
+                "wayinfo-test-no-such-session",
+                "/p/my file.rs",
+                "+10:5"
+            ]
+        );
+    }
+
+    #[test]
+    fn inputs() {
+        let loc = Location::parse("/p", "my file.rs:10:5");
+
+        assert_eq!(
+            texts(&Editor::Helix.inputs(&loc)),
+            [
+                "<Escape>",
+                ":o \"/p/my file.rs\"",
+                "<Enter>",
+                ":goto 10",
+                "<Enter>",
+                "gh",
+                "4l"
+            ]
+        );
+        assert_eq!(
+            texts(&Editor::Kak.inputs(&loc)),
+            [
+                "<Escape>",
+                ":edit -existing '/p/my file.rs' 10 5",
+                "<Enter>"
+            ]
+        );
+        assert_eq!(
+            texts(&Editor::Nvim.inputs(&loc)),
+            [
+                "<Escape>",
+                ":edit /p/my\\ file.rs",
+                "<Enter>",
+                ":call cursor(10, 5)",
+                "<Enter>"
+            ]
+        );
+        assert_eq!(
+            texts(&Editor::Vim.inputs(&Location::parse("/p", "a.rs"))),
+            ["<Escape>", ":edit /p/a.rs", "<Enter>"]
+        );
+    }
+
+    #[test]
+    fn quote() {
+        assert_eq!(helix_quote("/p/a.rs"), "/p/a.rs");
+        assert_eq!(helix_quote("/p/it's.rs"), "\"/p/it's.rs\"");
+        assert_eq!(helix_quote("/p/say \"hi\".rs"), "'/p/say \"hi\".rs'");
+        assert_eq!(kak_quote("it's"), "'it''s'");
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    env, fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use super::{tmux, Multiplexer, Tmux};
+use crate::{
+    config::{Config, WindowConfig},
+    errors::Error,
+    waybar::WaybarData,
+    Result,
+};
+
+/// tmux 会话
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub name: String,
+    /// 连接的客户端数量
+    pub attached: u32,
+    pub windows: u32,
+    /// 最后活动时间，秒
+    pub activity: i64,
+}
+
+/// 所有会话，tmux server 没有运行时为空
+fn sessions() -> Result<Vec<Session>> {
+    let output = match tmux(&[
+        "list-sessions",
+        "-F",
+        // 与 list-panes 相同用空格分隔，名称中可以有空格，放在最后
+        "#{session_attached} #{session_windows} #{session_activity} #{session_name}",
+    ]) {
+        Ok(output) => output,
+        Err(err) if no_server(err.as_ref()) => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let sessions = output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, ' ');
+            let attached = parts.next()?.parse().unwrap_or_default();
+            let windows = parts.next()?.parse().unwrap_or_default();
+            let activity = parts.next()?.parse().unwrap_or_default();
+            Some(Session {
+                name: parts.next()?.to_owned(),
+                attached,
+                windows,
+                activity,
+            })
+        })
+        .collect();
+
+    Ok(sessions)
+}
+
+fn no_server(err: &(dyn std::error::Error + 'static)) -> bool {
+    match err.downcast_ref::<Error>() {
+        Some(Error::Tmux { stderr, .. }) => {
+            stderr.starts_with("no server running") || stderr.starts_with("error connecting")
+        }
+        _ => false,
+    }
+}
+
+pub fn print_sessions() -> Result<()> {
+    let re = serde_json::to_string(&sessions()?).unwrap();
+    println!("{re}");
+    Ok(())
+}
+
+/// 会话数量，有连接的会话时添加 `attached` class
+pub fn waybar() -> Result<()> {
+    let sessions = sessions()?;
+
+    let mut class = "wayinfo-tmux".to_owned();
+    if sessions.is_empty() {
+        class.push_str(" empty");
+    } else if sessions.iter().any(|s| s.attached > 0) {
+        class.push_str(" attached");
+    }
+
+    let lines: Vec<String> = sessions
+        .iter()
+        .map(|s| {
+            let activity = DateTime::from_timestamp(s.activity, 0)
+                .map(|t| t.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            let attached = if s.attached > 0 { " *" } else { "" };
+            format!("{}\t{} windows\t{activity}{attached}", s.name, s.windows)
+        })
+        .collect();
+
+    let data = WaybarData {
+        class,
+        text: format!(" {}", sessions.len()),
+        alt: Some(sessions.len().to_string()),
+        tooltip: (!lines.is_empty()).then(|| lines.join("\n")),
+        percentage: None,
+    };
+
+    let re = serde_json::to_string(&data).unwrap();
+    println!("{re}");
+    Ok(())
+}
+
+/// 按目录名称创建会话，已经存在时直接切换
+pub fn project(dir: &str) -> Result<()> {
+    let dir = fs::canonicalize(dir)?;
+    let name = session_name(&dir);
+
+    if !has_session(&name) {
+        let layouts = Config::load()?.tmux.layouts;
+        let windows = layouts
+            .get(&name)
+            .or_else(|| layouts.get("default"))
+            .cloned()
+            .unwrap_or_default();
+        create(&name, &dir.to_string_lossy(), &windows)?;
+    }
+
+    switch(&name)
+}
+
+/// tmux 外时 attach，tmux 内时 switch-client
+pub fn switch(name: &str) -> Result<()> {
+    let target = format!("={name}");
+
+    if env::var_os("TMUX").is_some() {
+        tmux(&["switch-client", "-t", &target])?;
+        return Ok(());
+    }
+
+    // attach 需要使用当前终端
+    let status = Command::new("tmux")
+        .args(["attach-session", "-t", &target])
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()?;
+    if !status.success() {
+        return Error::Tmux {
+            status: status.code().unwrap_or(-1),
+            stderr: format!("can't attach to session {name}"),
+        }
+        .into();
+    }
+
+    Ok(())
+}
+
+/// 会话名称中不能有 `.` 和 `:`
+fn session_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().replace(['.', ':'], "_"))
+        .unwrap_or_else(|| "root".to_owned())
+}
+
+fn has_session(name: &str) -> bool {
+    tmux(&["has-session", "-t", &format!("={name}")]).is_ok()
+}
+
+/// 按配置创建窗口，命令在窗口的 shell 中执行，结束后保留 shell
+fn create(name: &str, dir: &str, windows: &[WindowConfig]) -> Result<()> {
+    let mut argv = vec![
+        "new-session",
+        "-d",
+        "-P",
+        "-F",
+        "#{window_id}",
+        "-s",
+        name,
+        "-c",
+        dir,
+    ];
+    let first = windows.first();
+    if let Some(window) = first.and_then(|w| w.name.as_deref()) {
+        argv.extend(["-n", window]);
+    }
+    let first_id = tmux(&argv)?.trim().to_owned();
+    if let Some(command) = first.and_then(|w| w.command.as_deref()) {
+        Tmux.send_line(&first_id, command)?;
+    }
+
+    let target = format!("={name}:");
+    for window in windows.iter().skip(1) {
+        let mut argv = vec![
+            "new-window",
+            "-d",
+            "-P",
+            "-F",
+            "#{window_id}",
+            "-t",
+            &target,
+            "-c",
+            dir,
+        ];
+        if let Some(name) = window.name.as_deref() {
+            argv.extend(["-n", name]);
+        }
+        let id = tmux(&argv)?.trim().to_owned();
+        if let Some(command) = window.command.as_deref() {
+            Tmux.send_line(&id, command)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,368 @@
+use std::{env, path::Path, process::Command};
+
+use super::{Input, Multiplexer, Pane};
+use crate::{errors::Error, Result};
+
+/// zellij，通过 `zellij action` 控制当前会话。
+/// pane 使用名称作为 id，只能输入到焦点 pane，输入后焦点留在目标 pane
+pub(super) struct Zellij;
+
+/// `dump-layout` 中当前 tab 可以切换焦点的 pane，不包括容器、插件和浮动 pane
+#[derive(Debug, PartialEq)]
+struct LayoutPane {
+    name: Option<String>,
+    /// 命令 pane 的程序，shell pane 为 `$SHELL`
+    command: String,
+    focus: bool,
+}
+
+impl Multiplexer for Zellij {
+    /// 有名称的 pane，不包括焦点 pane
+    fn panes(&self) -> Result<Vec<Pane>> {
+        let panes = layout()?
+            .into_iter()
+            .filter(|p| !p.focus)
+            .filter_map(|p| {
+                let name = p.name?;
+                Some(Pane {
+                    id: name.clone(),
+                    command: p.command,
+                    role: name,
+                })
+            })
+            .collect();
+        Ok(panes)
+    }
+
+    /// pane 名称为用途
+    fn split(
+        &self,
+        vertical: bool,
+        dir: Option<&str>,
+        role: &str,
+        command: &[&str],
+    ) -> Result<String> {
+        let direction = match vertical {
+            true => "down",
+            false => "right",
+        };
+
+        let mut argv = vec!["new-pane", "--direction", direction, "--name", role];
+        if let Some(dir) = dir {
+            argv.extend(["--cwd", dir]);
+        }
+        if !command.is_empty() {
+            argv.extend(["--close-on-exit", "--"]);
+            argv.extend(command);
+        }
+        zellij(&argv)?;
+
+        Ok(role.to_owned())
+    }
+
+    fn send_inputs(&self, target: &str, inputs: &[Input]) -> Result<()> {
+        focus(target)?;
+
+        for input in inputs {
+            match input {
+                Input::Text(text) => zellij(&["write-chars", "--", text])?,
+                Input::Key(key) => zellij(&["write", key_byte(key)?])?,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// zellij 不能按名称选择 pane，依次切换焦点直到名称相同，
+/// 最多切换当前 tab 中的 pane 数量次
+fn focus(name: &str) -> Result<()> {
+    let focused = |panes: &[LayoutPane]| {
+        panes
+            .iter()
+            .any(|p| p.focus && p.name.as_deref() == Some(name))
+    };
+
+    let mut panes = layout()?;
+    if panes.iter().any(|p| p.name.as_deref() == Some(name)) {
+        for _ in 1..panes.len() {
+            if focused(&panes) {
+                return Ok(());
+            }
+            zellij(&["focus-next-pane"])?;
+            panes = layout()?;
+        }
+        if focused(&panes) {
+            return Ok(());
+        }
+    }
+
+    Error::Zellij {
+        msg: format!("pane {name} not found"),
+    }
+    .into()
+}
+
+/// tmux 按键名对应的字节
+fn key_byte(key: &str) -> Result<&'static str> {
+    match key {
+        "Enter" => Ok("13"),
+        "Escape" => Ok("27"),
+        "Tab" => Ok("9"),
+        _ => Error::Zellij {
+            msg: format!("unsupported key {key}"),
+        }
+        .into(),
+    }
+}
+
+fn layout() -> Result<Vec<LayoutPane>> {
+    let shell = env::var("SHELL").unwrap_or_default();
+    Ok(parse_layout(&zellij(&["dump-layout"])?, &shell))
+}
+
+/// `dump-layout` 中的块
+enum Block {
+    Tab,
+    /// `panes` 中的序号
+    Pane(usize),
+    Floating,
+    /// 模板和 swap 布局，不是当前的 pane
+    Template,
+    Other,
+}
+
+/// 按行解析 `zellij action dump-layout` 的 KDL，返回焦点 tab 中的 pane，如
+/// `pane command="hx" name="hx" focus=true {`
+fn parse_layout(text: &str, shell: &str) -> Vec<LayoutPane> {
+    // (pane, tab 序号, 是否容器或插件)
+    let mut panes: Vec<(LayoutPane, usize, bool)> = Vec::new();
+    let mut stack: Vec<Block> = Vec::new();
+    let mut tabs = 0;
+    let mut focus_tab = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line == "}" {
+            stack.pop();
+            continue;
+        }
+
+        let node = line.split([' ', '{', ';']).next().unwrap_or_default();
+        // 同一行中的 `pane { plugin location="zellij:tab-bar"; }`
+        let opens = line.ends_with('{');
+        let skip = stack
+            .iter()
+            .any(|b| matches!(b, Block::Template | Block::Floating));
+
+        // 容器或插件中的子节点
+        if let Some(Block::Pane(parent)) = stack.last() {
+            if node == "pane" || node == "plugin" || node == "children" {
+                panes[*parent].2 = true;
+            }
+        }
+
+        let block = match node {
+            "tab" if !skip => {
+                tabs += 1;
+                if line.contains(" focus=true") {
+                    focus_tab = Some(tabs);
+                }
+                Block::Tab
+            }
+            "pane" if !skip => {
+                let command = attribute(line, "command").unwrap_or_else(|| shell.to_owned());
+                let pane = LayoutPane {
+                    name: attribute(line, "name"),
+                    command: basename(&command),
+                    focus: line.contains(" focus=true"),
+                };
+                let inline = line.contains('{') && !opens;
+                let leaf = !inline || !line.contains("plugin") && !line.contains("children");
+                panes.push((pane, tabs, !leaf));
+                Block::Pane(panes.len() - 1)
+            }
+            "floating_panes" => Block::Floating,
+            "pane_template"
+            | "tab_template"
+            | "new_tab_template"
+            | "swap_tiled_layout"
+            | "swap_floating_layout" => Block::Template,
+            _ => Block::Other,
+        };
+
+        if opens {
+            stack.push(block);
+        }
+    }
+
+    // 没有标记焦点时为第一个 tab
+    let tab = focus_tab.unwrap_or(1);
+    panes
+        .into_iter()
+        .filter(|(_, t, branch)| *t == tab && !branch)
+        .map(|(pane, _, _)| pane)
+        .collect()
+}
+
+/// KDL 属性 `key="value"`
+fn attribute(line: &str, key: &str) -> Option<String> {
+    let start = line.find(&format!(" {key}=\""))? + key.len() + 3;
+    let len = line[start..].find('"')?;
+    Some(line[start..start + len].to_owned())
+}
+
+fn basename(command: &str) -> String {
+    Path::new(command)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// `zellij action`，失败时返回 stderr
+fn zellij(args: &[&str]) -> Result<String> {
+    let output = Command::new("zellij").arg("action").args(args).output()?;
+
+    if !output.status.success() {
+        return Error::Zellij {
+            msg: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        }
+        .into();
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(name: Option<&str>, command: &str, focus: bool) -> LayoutPane {
+        LayoutPane {
+            name: name.map(str::to_owned),
+            command: command.to_owned(),
+            focus,
+        }
+    }
+
+    /// 默认布局，两个 tab，嵌套的分割，焦点在第二个 tab
+    const NESTED: &str = r#"layout {
+    cwd "/home/user/src/wayinfo"
+    tab name="Tab #1" hide_floating_panes=true {
+        pane size=1 borderless=true {
+            plugin location="zellij:tab-bar"
+        }
+        pane name="other" focus=true
+        pane size=2 borderless=true {
+            plugin location="zellij:status-bar"
+        }
+    }
+    tab name="Tab #2" focus=true hide_floating_panes=true {
+        pane size=1 borderless=true {
+            plugin location="zellij:tab-bar"
+        }
+        pane split_direction="vertical" {
+            pane command="/usr/bin/hx" cwd="src" name="hx" focus=true size="50%" {
+                args "."
+                start_suspended true
+            }
+            pane split_direction="horizontal" size="50%" {
+                pane name="run" size="50%"
+                pane cwd="/tmp" size="50%"
+            }
+        }
+        pane size=2 borderless=true {
+            plugin location="zellij:status-bar"
+        }
+        floating_panes {
+            pane name="scratch" x=10 y=5 width=80 height=20
+        }
+    }
+    new_tab_template {
+        pane size=1 borderless=true {
+            plugin location="zellij:tab-bar"
+        }
+        pane name="template-pane"
+        pane size=2 borderless=true {
+            plugin location="zellij:status-bar"
+        }
+    }
+    swap_tiled_layout name="vertical" {
+        tab max_panes=5 {
+            pane size=1 borderless=true {
+                plugin location="zellij:tab-bar"
+            }
+            pane {
+                pane split_direction="vertical" {
+                    pane name="swap-pane"
+                    pane { children; }
+                }
+            }
+        }
+    }
+}
+"#;
+
+    /// 用户布局中的模板，容器没有 split_direction，单行的插件 pane
+    const TEMPLATES: &str = r#"layout {
+    pane_template name="editor" {
+        pane name="template-editor" command="nvim"
+    }
+    tab_template name="ui" {
+        pane size=1 borderless=true { plugin location="zellij:compact-bar"; }
+        children
+    }
+    tab name="code" focus=true {
+        pane size=1 borderless=true { plugin location="zellij:compact-bar"; }
+        pane {
+            pane name="nvim" command="nvim" focus=true
+            pane name="run" { start_suspended true; }
+        }
+    }
+}
+"#;
+
+    #[test]
+    fn nested_splits() {
+        assert_eq!(
+            parse_layout(NESTED, "/bin/zsh"),
+            [
+                pane(Some("hx"), "hx", true),
+                pane(Some("run"), "zsh", false),
+                pane(None, "zsh", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn templates() {
+        assert_eq!(
+            parse_layout(TEMPLATES, "/bin/bash"),
+            [
+                pane(Some("nvim"), "nvim", true),
+                pane(Some("run"), "bash", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn without_focused_tab() {
+        let text = "layout {\n    tab {\n        pane name=\"a\"\n    }\n    tab {\n        pane name=\"b\"\n    }\n}\n";
+        assert_eq!(parse_layout(text, "sh"), [pane(Some("a"), "sh", false)]);
+    }
+
+    #[test]
+    fn attributes() {
+        let line = r#"pane command="/usr/bin/hx" cwd="src" name="hx" focus=true {"#;
+        assert_eq!(attribute(line, "command").as_deref(), Some("/usr/bin/hx"));
+        assert_eq!(attribute(line, "name").as_deref(), Some("hx"));
+        assert_eq!(attribute(line, "size"), None);
+        assert_eq!(basename("/usr/bin/hx"), "hx");
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(key_byte("Enter").unwrap(), "13");
+        assert_eq!(key_byte("Escape").unwrap(), "27");
+        assert!(key_byte("F1").is_err());
+    }
+}
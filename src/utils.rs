@@ -1,28 +1,90 @@
+use std::time::Duration;
+
 use crate::errors::Error;
-use std::process::{Command, Stdio};
-
-/// 执行
-pub fn shell_exec(cmd: &str) -> Result<String, Error> {
-    let shell = if cfg!(windows) {
-        vec!["cmd".to_owned(), "/C".to_owned()]
-    } else {
-        vec!["sh".to_owned(), "-c".to_owned()]
+
+/// 解析时间间隔，如 `500ms`, `2s`, `1m`, `1h`，纯数字为秒，不能为 0
+pub fn parse_duration(s: &str) -> Result<Duration, Error> {
+    match duration(s)? {
+        Duration::ZERO => Err(Error::Args {
+            msg: format!("duration must be greater than 0: {s}"),
+        }),
+        d => Ok(d),
+    }
+}
+
+/// 与 [`parse_duration`] 相同，可以为 0
+pub fn parse_ttl(s: &str) -> Result<Duration, Error> {
+    duration(s)
+}
+
+fn duration(s: &str) -> Result<Duration, Error> {
+    let invalid = |what: &str| Error::Args {
+        msg: format!("{what}: {s}"),
     };
 
-    let mut process = Command::new(&shell[0]);
+    let s = s.trim();
+    let pos = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(pos);
 
-    process.args(&shell[1..]).arg(cmd).stdin(Stdio::piped());
-    // .stdout(Stdio::piped());
+    let num: f64 = num.parse().map_err(|_| invalid("invalid duration"))?;
 
-    let out = match process.output() {
-        Ok(out) => out,
-        Err(e) => {
-            log::error!("Failed to start shell: {}", e);
-            return Err(e.into());
-        }
+    let secs = match unit.trim() {
+        "ms" => num / 1000.0,
+        "" | "s" => num,
+        "m" => num * 60.0,
+        "h" => num * 3600.0,
+        "d" => num * 86400.0,
+        _ => return Err(invalid("invalid duration unit")),
     };
 
-    let output_str = String::from_utf8(out.stdout).unwrap();
+    if !secs.is_finite() || secs < 0.0 {
+        return Err(invalid("invalid duration"));
+    }
+    Duration::try_from_secs_f64(secs).map_err(|_| invalid("duration out of range"))
+}
+
+/// 替换模板中的 `{name}`，未知的变量保持不变
+pub fn render(template: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(template.to_owned(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_units() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration(" 1.5m ").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+    }
+
+    #[test]
+    fn duration_invalid() {
+        for s in [
+            "",
+            "-1s",
+            "inf",
+            "NaN",
+            "1x",
+            "1.2.3s",
+            "99999999999999999999999d",
+        ] {
+            assert!(parse_duration(s).is_err(), "{s}");
+        }
+    }
 
-    Ok(output_str)
+    #[test]
+    fn duration_zero() {
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0ms").is_err());
+        assert_eq!(parse_ttl("0").unwrap(), Duration::ZERO);
+    }
 }
@@ -1,15 +1,11 @@
-use std::{
-    io::{self, Write},
-    thread,
-    time::Duration,
-};
-
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 
 /// waybar custom
 /// https://github.com/Alexays/Waybar/wiki/Module:-Custom
 #[derive(Debug, Clone, Serialize)]
 pub struct WaybarData {
+    /// 空格分隔多个 class，输出为数组
+    #[serde(serialize_with = "serialize_class")]
     pub class: String,
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -20,11 +16,10 @@ pub struct WaybarData {
     pub percentage: Option<usize>,
 }
 
-pub fn loop_stdout(data: WaybarData, sleep: Duration) {
-    let re = serde_json::to_string(&data).unwrap();
-    loop {
-        println!("{}", re);
-        io::stdout().flush().unwrap();
-        thread::sleep(sleep);
+fn serialize_class<S: Serializer>(class: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    let classes: Vec<&str> = class.split_whitespace().collect();
+    match classes.as_slice() {
+        [one] => serializer.serialize_str(one),
+        _ => classes.serialize(serializer),
     }
 }
@@ -1,84 +1,689 @@
-use std::fs;
+mod air;
+mod alert;
+mod amap;
+mod cache;
+mod condition;
+mod geo;
+mod key;
+mod locale;
+#[cfg(test)]
+mod mock;
+mod open_meteo;
+mod owm;
+mod sun;
+mod wttr;
+
 use std::str::FromStr;
+use std::time::Duration;
 
-use clap::Args;
-use serde::{Deserialize, Deserializer, Serialize};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 
-use crate::{errors::Error, waybar::WaybarData, Result};
+use self::air::Air;
+use self::alert::Alert;
+use self::cache::{age_text, Cache};
+use self::condition::Condition;
+use self::geo::{Place, LOCATE_URL};
+use self::key::api_key;
+use self::locale::{Lang, Units};
+use crate::{
+    errors::Error,
+    utils::{parse_ttl, render},
+    waybar::WaybarData,
+    Result,
+};
 
 #[derive(Args)]
 pub(crate) struct WeatherArgs {
-    /// 城市
-    #[arg(short, long)]
-    pub city: String,
+    #[command(subcommand)]
+    pub command: Option<WeatherCommands>,
+
+    /// 城市名称、adcode 或 `纬度,经度`，名称会查询后缓存
+    #[arg(short, long, required_unless_present = "auto_locate")]
+    pub city: Option<String>,
+
+    /// 根据 IP 定位城市
+    #[arg(long)]
+    pub auto_locate: bool,
+
+    /// IP 定位接口，默认高德使用高德 IP 定位，其他使用 ipinfo.io
+    #[arg(long)]
+    pub locate_url: Option<String>,
 
-    /// 接口密钥
+    /// 天气数据源
+    #[arg(short, long, value_enum, default_value_t = ProviderKind::Amap)]
+    pub provider: ProviderKind,
+
+    /// 替换数据源接口地址，如 `http://127.0.0.1:8080`
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// 接口密钥，会显示在进程列表中，建议使用环境变量、配置文件或 Secret Service
     #[arg(short, long)]
     pub key: Option<String>,
 
-    /// 接口密钥文件
+    /// 接口密钥文件，去掉首尾空白
     #[arg(long)]
     pub key_file: Option<String>,
 
+    /// 第几天，1 为今天，超出时使用最后一天
     #[arg(short, long, default_value_t = 1)]
     pub day: usize,
 
+    /// 输出前几天的预报 JSON 数组，如用于 eww
+    #[arg(long, conflicts_with = "waybar")]
+    pub days: Option<usize>,
+
+    /// 白天或夜间天气，auto 根据日出日落或当前时间选择
+    #[arg(long, value_enum, default_value_t = Period::Auto)]
+    pub period: Period,
+
+    /// 实况天气、预报或者两者，both 文本显示实况，tooltip 显示预报
+    #[arg(short, long, value_enum, default_value_t = Mode::Forecast)]
+    pub mode: Mode,
+
+    /// 温度不低于该值时添加 `hot` class
+    #[arg(long, default_value_t = 30.0)]
+    pub hot: f64,
+
+    /// 温度不高于该值时添加 `cold` class
+    #[arg(long, default_value_t = 5.0)]
+    pub cold: f64,
+
+    /// 缓存有效时间，如 `10m`，`0` 为每次都请求，请求失败时仍然使用缓存
+    #[arg(long, value_parser = parse_ttl, default_value = "10m")]
+    pub ttl: Duration,
+
+    /// 网络错误时的重试次数
+    #[arg(long, default_value_t = 2)]
+    pub retries: u32,
+
+    /// 显示气象预警，有预警时添加 `alert` class
+    #[arg(long)]
+    pub alerts: bool,
+
+    /// 新的气象预警发送桌面通知
+    #[arg(long)]
+    pub notify: bool,
+
+    /// 显示空气质量指数，添加 AQI 等级 class
+    #[arg(long)]
+    pub aqi: bool,
+
+    /// waybar 文本模板，如 `{icon} {condition} {temp}{unit}`
+    #[arg(long)]
+    pub format: Option<String>,
+
+    /// waybar tooltip 模板
+    #[arg(long)]
+    pub tooltip_format: Option<String>,
+
+    /// 温度单位
+    #[arg(long, value_enum, default_value_t = Units::Metric)]
+    pub units: Units,
+
+    /// 天气、星期和标签的语言
+    #[arg(long, value_enum, default_value_t = Lang::Zh)]
+    pub lang: Lang,
+
     // #[arg(from_global)]
     #[arg(long)]
     pub waybar: bool,
 }
 
+#[derive(Subcommand)]
+pub(crate) enum WeatherCommands {
+    /// 城市
+    City {
+        #[command(subcommand)]
+        command: CityCommands,
+    },
+    /// 气象预警，目前仅 openweathermap One Call 3.0 支持
+    Alerts,
+    /// 空气质量，AQI、PM2.5、PM10 和 O3，数据来自 open-meteo
+    Aqi,
+    /// 今天的日出日落、昼长和月相，根据城市坐标计算
+    Sun,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum CityCommands {
+    /// 查询城市的 adcode 或坐标
+    Search {
+        /// 城市名称
+        name: String,
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ProviderKind {
+    /// 高德，仅支持中国，需要 key
+    Amap,
+    /// open-meteo.com
+    OpenMeteo,
+    /// wttr.in
+    Wttr,
+    /// openweathermap.org，需要 key
+    Owm,
+}
+
+impl ProviderKind {
+    /// 命令行中的名称，如 `open-meteo`
+    fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_owned())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Mode {
+    /// 实况天气
+    Live,
+    /// 预报天气
+    Forecast,
+    /// 实况和预报
+    Both,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Period {
+    /// 白天
+    Day,
+    /// 夜间
+    Night,
+    /// 自动
+    Auto,
+}
+
+impl Period {
+    fn as_str(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Night => "night",
+            Period::Auto => "auto",
+        }
+    }
+
+    /// auto 只对今天生效，`now` 为城市当前时间
+    fn resolve(self, cast: &Forecast, now: NaiveDateTime) -> Period {
+        if self != Period::Auto {
+            return self;
+        }
+
+        if cast.date != now.format("%Y-%m-%d").to_string() {
+            return Period::Day;
+        }
+
+        self.at(now.time(), &cast.sunrise, &cast.sunset)
+    }
+
+    /// `now` 与日出日落为同一时区，日出日落未知时 6:00 - 18:00 为白天
+    fn at(self, now: NaiveTime, sunrise: &Option<String>, sunset: &Option<String>) -> Period {
+        if self != Period::Auto {
+            return self;
+        }
+
+        let time = |t: &Option<String>, default: u32| {
+            t.as_deref()
+                .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+                .unwrap_or(NaiveTime::from_hms_opt(default, 0, 0).unwrap())
+        };
+        let sunrise = time(sunrise, 6);
+        let sunset = time(sunset, 18);
+
+        if now >= sunrise && now < sunset {
+            Period::Day
+        } else {
+            Period::Night
+        }
+    }
+}
+
+/// 天气数据源
+trait Provider {
+    /// 逐日预报，第一天为今天
+    fn forecast(&self, city: &str) -> Result<Vec<Forecast>>;
+
+    /// 实况天气
+    fn live(&self, _city: &str) -> Result<Live> {
+        Error::WeatherUnsupportedError { what: "live" }.into()
+    }
+
+    /// 查询城市
+    fn search(&self, _name: &str) -> Result<Vec<Place>> {
+        Error::WeatherUnsupportedError { what: "search" }.into()
+    }
+
+    /// 气象预警
+    fn alerts(&self, _city: &str) -> Result<Vec<Alert>> {
+        Error::WeatherUnsupportedError { what: "alerts" }.into()
+    }
+
+    /// 城市坐标，默认查询城市
+    fn coordinates(&self, city: &str) -> Result<(f64, f64)> {
+        if let Some(coordinates) = geo::coordinates(city) {
+            return Ok(coordinates);
+        }
+
+        match self.search(city)?.into_iter().find_map(|p| p.coordinates) {
+            Some(coordinates) => Ok(coordinates),
+            None => Error::WeatherFailError.into(),
+        }
+    }
+
+    /// IP 定位，返回城市参数，默认为 `纬度,经度`
+    fn locate(&self, url: Option<&str>) -> Result<String> {
+        geo::locate_ip(url.unwrap_or(LOCATE_URL)).map(|loc| loc.coordinates())
+    }
+}
+
+/// `wayinfo-weather` 以及天气状况，如 `rain`
+fn condition_class(weather: &str) -> String {
+    match Condition::of(weather) {
+        Some(condition) => format!("wayinfo-weather {}", condition.as_str()),
+        None => "wayinfo-weather".to_owned(),
+    }
+}
+
+/// 添加温度 class `hot` 或 `cold`
+fn add_temp_class(data: &mut WaybarData, temp: f64, args: &WeatherArgs) {
+    if temp >= args.hot {
+        data.class.push_str(" hot");
+    } else if temp <= args.cold {
+        data.class.push_str(" cold");
+    }
+}
+
+/// 数据源和缓存
+struct Client {
+    provider: Box<dyn Provider>,
+    kind: ProviderKind,
+    cache: Cache,
+    base_url: Option<String>,
+    lang: Lang,
+}
+
+impl Client {
+    fn new(args: &WeatherArgs) -> Result<Self> {
+        Ok(Client {
+            provider: provider(args)?,
+            kind: args.provider,
+            cache: Cache::new(&args.provider.name(), args.ttl, args.retries),
+            base_url: args.base_url.clone(),
+            lang: args.lang,
+        })
+    }
+
+    /// 空气质量、坐标和时区使用的 open-meteo 地址，`--base-url` 只在数据源为 open-meteo 时替换
+    fn open_meteo_url(&self) -> Option<String> {
+        match self.kind {
+            ProviderKind::OpenMeteo => self.base_url.clone(),
+            _ => None,
+        }
+    }
+
+    /// 城市参数，名称查询为 adcode 或坐标，不支持查询的数据源直接使用名称。
+    /// 子命令不检查 `--city`，这里要求 `--city` 或 `--auto-locate`，不会自动定位
+    fn city(&self, args: &WeatherArgs) -> Result<String> {
+        let city = match &args.city {
+            Some(city) if !args.auto_locate => city.trim().to_owned(),
+            _ if args.auto_locate => self.cache.fetch("locate", || {
+                self.provider.locate(args.locate_url.as_deref())
+            })?,
+            _ => {
+                return Error::Args {
+                    msg: "--city or --auto-locate is required".to_owned(),
+                }
+                .into()
+            }
+        };
+
+        if geo::is_resolved(&city) {
+            return Ok(city);
+        }
+
+        self.cache
+            .city(&city, || match self.provider.search(&city) {
+                Ok(places) => match places.into_iter().next() {
+                    Some(place) => Ok(Some(place.city)),
+                    None => Error::WeatherFailError.into(),
+                },
+                Err(err) if unsupported(err.as_ref()) => Ok(None),
+                Err(err) => Err(err),
+            })
+    }
+
+    /// 逐日预报，不能为空
+    fn forecast(&self, city: &str) -> Result<Vec<Forecast>> {
+        let casts = self
+            .cache
+            .fetch_marked(&format!("{city}-forecast"), || self.provider.forecast(city))?;
+
+        if casts.is_empty() {
+            return Error::WeatherFailError.into();
+        }
+
+        Ok(casts)
+    }
+
+    fn live(&self, city: &str) -> Result<Live> {
+        self.cache
+            .fetch_marked(&format!("{city}-live"), || self.provider.live(city))
+    }
+
+    /// 添加空气质量、预警和缓存状态后输出
+    fn print_waybar(&self, args: &WeatherArgs, city: &str, mut data: WaybarData, air: Option<Air>) {
+        if let Some(air) = &air {
+            air.mark(args, &mut data);
+        }
+        if args.alerts {
+            self.mark_alerts(args, city, &mut data);
+        }
+        self.mark_stale(&mut data);
+
+        let re = serde_json::to_string(&data).unwrap();
+        println!("{}", re);
+    }
+
+    /// 使用过期缓存时，添加 `stale` class 和缓存时间
+    fn mark_stale(&self, data: &mut WaybarData) {
+        if let Some(age) = self.cache.stale() {
+            data.class.push_str(" stale");
+            let line = match self.lang {
+                Lang::Zh => format!("缓存于 {} 前", age_text(age, self.lang)),
+                Lang::En => format!("Cached {} ago", age_text(age, self.lang)),
+            };
+            data.tooltip = Some(match data.tooltip.take() {
+                Some(tooltip) => format!("{tooltip}\n\n{line}"),
+                None => line,
+            });
+        }
+    }
+}
+
+fn unsupported(err: &(dyn std::error::Error + 'static)) -> bool {
+    matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::WeatherUnsupportedError { .. })
+    )
+}
+
+fn provider(args: &WeatherArgs) -> Result<Box<dyn Provider>> {
+    let base_url = args.base_url.clone();
+
+    let provider: Box<dyn Provider> = match args.provider {
+        ProviderKind::Amap => Box::new(amap::Amap {
+            key: api_key(args)?,
+            base_url,
+        }),
+        ProviderKind::OpenMeteo => Box::new(open_meteo::OpenMeteo { base_url }),
+        ProviderKind::Wttr => Box::new(wttr::Wttr { base_url }),
+        ProviderKind::Owm => Box::new(owm::Owm {
+            key: api_key(args)?,
+            base_url,
+        }),
+    };
+
+    Ok(provider)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Weather {
     date: String,
     week: String,
+    period: Period,
     icon: String,
     weather: String,
     temp: String,
     temp_float: f64,
     wind: String,
     power: String,
+    temp_min: f64,
+    temp_max: f64,
+    sunrise: Option<String>,
+    sunset: Option<String>,
+}
+
+/// 模板变量
+type Values = Vec<(&'static str, String)>;
+
+/// 预报默认文本
+const FORECAST_FORMAT: &str = "{icon} {condition} {temp_min}~{temp_max}{unit}";
+/// 实况默认文本
+const LIVE_FORMAT: &str = "{icon} {condition} {temp}{unit}";
+
+impl Weather {
+    /// 预报没有体感温度和湿度，模板中保持 `{feels_like}` 和 `{humidity}` 不变
+    fn values(&self, args: &WeatherArgs, city: &str) -> Values {
+        let temp = |t: f64| format!("{:.0}", args.units.temp(t));
+
+        vec![
+            ("icon", self.icon.clone()),
+            ("condition", args.lang.tr(&self.weather)),
+            ("temp", temp(self.temp_float)),
+            ("temp_min", temp(self.temp_min)),
+            ("temp_max", temp(self.temp_max)),
+            ("wind", args.lang.tr(&self.wind)),
+            ("power", self.power.clone()),
+            ("date", self.date.clone()),
+            ("week", args.lang.week(&self.week)),
+            ("city", city.to_owned()),
+            ("unit", args.units.symbol().to_owned()),
+        ]
+    }
+}
+
+/// 实况天气
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Live {
+    city: String,
+    icon: String,
+    weather: String,
+    temp: String,
+    temp_float: f64,
+    /// 体感温度
+    #[serde(default)]
+    feels_like: Option<f64>,
+    /// 相对湿度 %
+    humidity: String,
+    wind: String,
+    power: String,
+    /// 数据发布时间
+    report_time: String,
+}
+
+impl Live {
+    fn values(&self, args: &WeatherArgs) -> Values {
+        let temp = |t: f64| format!("{:.0}", args.units.temp(t));
+        let date = self.report_time.get(..10).unwrap_or_default();
+
+        let mut values = vec![
+            ("icon", self.icon.clone()),
+            ("condition", args.lang.tr(&self.weather)),
+            ("temp", temp(self.temp_float)),
+            ("temp_min", temp(self.temp_float)),
+            ("temp_max", temp(self.temp_float)),
+            ("wind", args.lang.tr(&self.wind)),
+            ("power", self.power.clone()),
+            ("date", date.to_owned()),
+            ("week", args.lang.week(&week_of(date))),
+            ("city", self.city.clone()),
+            ("unit", args.units.symbol().to_owned()),
+        ];
+        // 数据源没有提供时保持模板不变
+        if let Some(feels_like) = self.feels_like {
+            values.push(("feels_like", temp(feels_like)));
+        }
+        if !self.humidity.is_empty() {
+            values.push(("humidity", self.humidity.clone()));
+        }
+        values
+    }
+
+    fn tooltip(&self, args: &WeatherArgs) -> String {
+        let lang = args.lang;
+        let temp = |t: f64| format!("{:.0}{}", args.units.temp(t), args.units.symbol());
+
+        let mut lines = vec![format!(
+            "{} {} {} {}",
+            self.city,
+            self.icon,
+            lang.tr(&self.weather),
+            temp(self.temp_float)
+        )];
+        if let Some(feels_like) = self.feels_like {
+            lines.push(format!("{} {}", lang.tr("体感"), temp(feels_like)));
+        }
+        if !self.humidity.is_empty() {
+            lines.push(format!("{} {}%", lang.tr("湿度"), self.humidity));
+        }
+        lines.push(lang.wind(&self.wind, &self.power));
+        lines.push(format!("{} {}", lang.tr("发布"), self.report_time));
+
+        lines.join("\n")
+    }
 }
 
-impl From<Weather> for WaybarData {
-    fn from(val: Weather) -> Self {
-        // class 根据不同的条件提供不同的 class
-        let class = "wayinfo-weather-sun";
+impl Weather {
+    fn new(value: Forecast, period: Period, now: NaiveDateTime) -> Self {
+        let temp_min = value.day_temp_float.min(value.night_temp_float);
+        let temp_max = value.day_temp_float.max(value.night_temp_float);
 
-        WaybarData {
-            text: format!("{} {} {}󰔄", val.icon, val.weather, val.temp),
-            alt: Some(format!("{} {}", val.wind, val.power)),
-            tooltip: None,
-            class: class.to_owned(),
-            percentage: None,
+        match period.resolve(&value, now) {
+            Period::Night => Weather {
+                date: value.date,
+                week: value.week,
+                period: Period::Night,
+                icon: value.night_icon,
+                weather: value.night_weather,
+                temp: value.night_temp,
+                temp_float: value.night_temp_float,
+                wind: value.night_wind,
+                power: value.night_power,
+                temp_min,
+                temp_max,
+                sunrise: value.sunrise,
+                sunset: value.sunset,
+            },
+            _ => Weather {
+                date: value.date,
+                week: value.week,
+                period: Period::Day,
+                icon: value.day_icon,
+                weather: value.day_weather,
+                temp: value.day_temp,
+                temp_float: value.day_temp_float,
+                wind: value.day_wind,
+                power: value.day_power,
+                temp_min,
+                temp_max,
+                sunrise: value.sunrise,
+                sunset: value.sunset,
+            },
         }
     }
 }
 
-impl From<Forecast> for Weather {
-    fn from(value: Forecast) -> Self {
-        // Todo: Night
-        let day_icon = weather_icon(&value.day_weather).to_owned();
-        Weather {
-            date: value.date,
-            week: value.week,
-            icon: day_icon,
-            weather: value.day_weather,
-            temp: value.day_temp,
-            temp_float: value.day_temp_float,
-            wind: value.day_wind,
-            power: value.day_power,
+impl Forecast {
+    /// 白天和夜间
+    fn tooltip(&self, args: &WeatherArgs) -> String {
+        let lang = args.lang;
+        let temp = |t: f64| format!("{:.0}{}", args.units.temp(t), args.units.symbol());
+
+        let mut lines = vec![
+            format!(
+                "{} {} {} {} {}",
+                lang.tr("白天"),
+                self.day_icon,
+                lang.tr(&self.day_weather),
+                temp(self.day_temp_float),
+                lang.wind(&self.day_wind, &self.day_power)
+            ),
+            format!(
+                "{} {} {} {} {}",
+                lang.tr("夜间"),
+                self.night_icon,
+                lang.tr(&self.night_weather),
+                temp(self.night_temp_float),
+                lang.wind(&self.night_wind, &self.night_power)
+            ),
+        ];
+
+        if let (Some(sunrise), Some(sunset)) = (&self.sunrise, &self.sunset) {
+            lines.push(format!(
+                "{} {sunrise} {} {sunset}",
+                lang.tr("日出"),
+                lang.tr("日落")
+            ));
         }
+
+        lines.join("\n")
     }
+
+    /// 表格中的一行，使用 tab 对齐
+    fn row(&self, args: &WeatherArgs) -> String {
+        let lang = args.lang;
+        let temp = |t: f64| format!("{:.0}", args.units.temp(t));
+        let date = self.date.get(5..).unwrap_or(&self.date);
+
+        format!(
+            "{date}\t{}\t{} {}\t{} {}\t{}~{}{}\t{}",
+            lang.week(&self.week),
+            self.day_icon,
+            lang.tr(&self.day_weather),
+            self.night_icon,
+            lang.tr(&self.night_weather),
+            temp(self.night_temp_float),
+            temp(self.day_temp_float),
+            args.units.symbol(),
+            lang.wind(&self.day_wind, &self.day_power),
+        )
+    }
+}
+
+/// 所选日期的白天夜间，以及所有日期的预报表格
+fn tooltip(cast: &Forecast, casts: &[Forecast], args: &WeatherArgs) -> String {
+    let mut lines = vec![cast.tooltip(args), String::new()];
+    lines.extend(casts.iter().map(|c| c.row(args)));
+    lines.join("\n")
 }
 
-/// 高德
+/// 模板或默认文本，class 为天气状况和温度，
+/// `--aqi` 时默认文本最后添加 AQI
+fn waybar(
+    args: &WeatherArgs,
+    format: &str,
+    values: &Values,
+    weather: &str,
+    temp: f64,
+) -> WaybarData {
+    let format = match (&args.format, args.aqi) {
+        (Some(format), _) => format.clone(),
+        (None, true) => format!("{format} 󰵃 {{aqi}}"),
+        (None, false) => format.to_owned(),
+    };
+
+    let mut data = WaybarData {
+        class: condition_class(weather),
+        text: render(&format, values),
+        alt: Some(render("{wind} {power}", values)),
+        tooltip: None,
+        percentage: None,
+    };
+    add_temp_class(&mut data, temp, args);
+    data
+}
+
+/// 逐日预报，字段与高德一致，其他数据源转换为该结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Forecast {
     date: String,
     week: String,
 
-    #[serde(skip_deserializing)]
+    #[serde(default)]
     day_icon: String,
     #[serde(alias = "dayweather")]
     day_weather: String,
@@ -91,7 +696,7 @@ struct Forecast {
     #[serde(alias = "daytemp_float", deserialize_with = "parse_float")]
     day_temp_float: f64,
 
-    #[serde(skip_deserializing)]
+    #[serde(default)]
     night_icon: String,
     #[serde(alias = "nightweather")]
     night_weather: String,
@@ -103,82 +708,44 @@ struct Forecast {
     night_power: String,
     #[serde(alias = "nighttemp_float", deserialize_with = "parse_float")]
     night_temp_float: f64,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Forecasts {
-    city: String,
-    adcode: String,
-    province: String,
-    reporttime: String,
-    casts: Vec<Forecast>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct WeatherData {
-    status: String,
-    count: String,
-    info: String,
-    infocode: String,
-    forecasts: Vec<Forecasts>,
-}
-
-fn weather_icon(s: &str) -> &str {
-    match s {
-        "晴" => "󰖙",
-        "少云" => "󰖐",
-        "晴间多云" => "󰖕",
-        "多云" => "󰼯",
-        "阴" => "󰼰",
-        "有风" | "平静" | "微风" | "和风" | "清风" => "",
-        "强风/劲风" | "疾风" | "大风" => "󰖝",
-        "烈风" | "风暴" | "狂爆风" => "󰼸",
-        "飓风" | "热带风暴" | "龙卷风" => "󰢘",
-        "霾" | "中度霾" | "重度霾" | "严重霾" => "󰖑",
-        "阵雨" => "󰖓",
-        "雷阵雨" | "雷阵雨并伴有冰雹" => "󰙾",
-        "毛毛雨/细雨" | "雨" | "小雨" => "󰖒",
-        "中雨" | "大雨" | "小雨-中雨" | "中雨-大雨" | "大雨-暴雨" => "󰖗",
-        "暴雨"
-        | "大暴雨"
-        | "特大暴雨"
-        | "强阵雨"
-        | "强雷阵雨"
-        | "极端降雨"
-        | "暴雨-大暴雨"
-        | "大暴雨-特大暴雨" => "󰖖",
-        "雨雪天气" | "雨夹雪" | "阵雨夹雪" | "冻雨" => "󰙿",
-        "阵雪" => "󰼴",
-        "雪" | "小雪" | "中雪" | "小雪-中雪" => "󰖘",
-        "大雪" | "暴雪" | "中雪-大雪" | "大雪-暴雪" => "󰼶",
-        "浮尘" | "扬沙" | "沙尘暴" | "强沙尘暴" => "",
-        "雾" | "浓雾" | "强浓雾" | "轻雾" | "大雾" | "特强浓雾" => "󰖑",
-
-        "热" => "󰖙 ",
-        "冷" => "",
-
-        // "未知"=>"未知",
-        _ => s,
-    }
+    /// 日出 `HH:MM`，当地时间
+    #[serde(default)]
+    sunrise: Option<String>,
+    /// 日落 `HH:MM`，当地时间
+    #[serde(default)]
+    sunset: Option<String>,
 }
 
+/// 高德为字符串，缓存中为数字
 fn parse_float<'de, D>(deserializer: D) -> std::result::Result<f64, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s: String = Deserialize::deserialize(deserializer)?;
-    f64::from_str(&s).map_err(serde::de::Error::custom)
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Float {
+        Str(String),
+        Num(f64),
+    }
+
+    match Float::deserialize(deserializer)? {
+        Float::Str(s) => f64::from_str(&s).map_err(serde::de::Error::custom),
+        Float::Num(n) => Ok(n),
+    }
 }
 
-fn get_weather(api_key: &str, city: &str) -> Result<WeatherData> {
-    // 构建API请求URL
-    let url = format!(
-        "https://restapi.amap.com/v3/weather/weatherInfo?key={}&city={}&extensions=all&output=json",
-        api_key, city
-    );
+/// 接口地址，`base_url` 替换默认的 `host`
+fn endpoint(base_url: &Option<String>, host: &str, path: &str) -> String {
+    let host = base_url.as_deref().unwrap_or(host).trim_end_matches('/');
+    format!("{host}{path}")
+}
 
-    // 发送请求
-    let response = reqwest::blocking::get(url)?;
+fn get_json<T: DeserializeOwned>(url: &str, query: &[(&str, &str)]) -> Result<T> {
+    let response = reqwest::blocking::Client::new()
+        .get(url)
+        .query(query)
+        .send()?;
 
     if !response.status().is_success() {
         return Error::WeatherResponseError {
@@ -187,58 +754,292 @@ fn get_weather(api_key: &str, city: &str) -> Result<WeatherData> {
         .into();
     }
 
-    let data: WeatherData = response.json()?;
+    let data: T = response.json()?;
     Ok(data)
 }
 
+/// 星期，1 为周一，与高德一致
+fn week_of(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.weekday().number_from_monday().to_string())
+        .unwrap_or_default()
+}
+
+/// 夜间图标，晴和少云使用月亮
+fn night_icon(icon: &str) -> &str {
+    match icon {
+        "󰖙" => "󰖔",
+        "󰖐" | "󰖕" => "󰼱",
+        _ => icon,
+    }
+}
+
+/// 风向角度转换为方向
+fn wind_direction(deg: f64) -> String {
+    const DIRECTIONS: [&str; 8] = ["北", "东北", "东", "东南", "南", "西南", "西", "西北"];
+    let i = ((deg.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8;
+    DIRECTIONS[i].to_owned()
+}
+
+/// 风速 km/h 转换为蒲福风级
+fn wind_power(kmh: f64) -> String {
+    const LIMITS: [f64; 12] = [
+        1.0, 6.0, 12.0, 20.0, 29.0, 39.0, 50.0, 62.0, 75.0, 89.0, 103.0, 118.0,
+    ];
+    let level = LIMITS.iter().take_while(|&&l| kmh >= l).count();
+    level.to_string()
+}
+
 pub fn parse(args: &WeatherArgs) -> Result<()> {
-    // let city = matches.value_of("city").unwrap();
-    let city = args.city.clone();
+    let client = Client::new(args)?;
 
-    if args.key.is_none() && args.key_file.is_none() {
-        return Error::WeatherKeyError.into();
+    if let Some(WeatherCommands::City { command }) = &args.command {
+        return match command {
+            CityCommands::Search { name } => search(&client, name),
+        };
     }
 
-    let api_key = match args.key.clone() {
-        Some(key) => key,
-        None => match args.key_file.clone() {
-            Some(key_file) => match fs::read_to_string(key_file) {
-                Ok(s) => s,
-                Err(_err) => return Error::WeatherKeyError.into(),
-            },
-            None => String::new(),
-        },
-    };
+    // let city = matches.value_of("city").unwrap();
+    let city = client.city(args)?;
 
-    if api_key.is_empty() {
-        return Error::WeatherKeyError.into();
+    match &args.command {
+        Some(WeatherCommands::Alerts) => return alert::print(args, &client, &city),
+        Some(WeatherCommands::Aqi) => return air::print(args, &client, &city),
+        Some(WeatherCommands::Sun) => return sun::print(args, &client, &city),
+        _ => {}
     }
 
-    let data = get_weather(&api_key, &city)?;
+    if args.mode != Mode::Forecast && args.days.is_none() {
+        return parse_live(args, &client, &city);
+    }
 
-    // let json_output = serde_json::to_string(&weather_data).unwrap();
+    let mut casts = client.forecast(&city)?;
+    client.fill_sun(&city, &mut casts);
 
-    if data.status != "1" || data.forecasts.is_empty() || data.forecasts[0].casts.is_empty() {
-        return Error::WeatherFailError.into();
+    if let Some(days) = args.days {
+        let days = &casts[..days.min(casts.len())];
+        let re = serde_json::to_string(days).unwrap();
+        println!("{}", re);
+        return Ok(());
     }
 
-    let casts = &data.forecasts[0].casts;
-    let data_day = match args.day {
-        i if casts.len() >= i - 1 => casts.get(i - 1).expect("forecast list is empty"),
-        _ => casts.last().unwrap(),
-    };
+    // --day 从 1 开始，0 也视为今天
+    let data_day = casts
+        .get(args.day.saturating_sub(1))
+        .unwrap_or(&casts[casts.len() - 1]);
 
-    let data: Weather = data_day.clone().into();
+    let data = Weather::new(data_day.clone(), args.period, client.now(args, &city));
+    let mut values = data.values(args, args.city.as_deref().unwrap_or(&city));
+    client.sun_values(args, &city, &data.date, &mut values);
+    let air = client.air_values(args, &city, &mut values);
 
     if args.waybar {
-        // loop_stdout(data.into(), Duration::from_secs(5));
-        let data: WaybarData = data.into();
+        let mut waybar = waybar(
+            args,
+            FORECAST_FORMAT,
+            &values,
+            &data.weather,
+            data.temp_float,
+        );
+        waybar.tooltip = Some(match &args.tooltip_format {
+            Some(format) => render(format, &values),
+            None => tooltip(data_day, &casts, args),
+        });
+        client.print_waybar(args, &city, waybar, air);
+    } else if let Some(format) = &args.format {
+        println!("{}", render(format, &values));
+    } else {
         let re = serde_json::to_string(&data).unwrap();
         println!("{}", re);
+    }
+
+    Ok(())
+}
+
+/// 实况天气，both 时 tooltip 附带预报
+fn parse_live(args: &WeatherArgs, client: &Client, city: &str) -> Result<()> {
+    let mut live = client.live(city)?;
+
+    let mut casts = match args.mode {
+        Mode::Both => client.forecast(city)?,
+        _ => Vec::new(),
+    };
+    client.fill_sun(city, &mut casts);
+
+    let now = client.now(args, city);
+    let today = now.format("%Y-%m-%d").to_string();
+    let (sunrise, sunset) = match casts.first() {
+        Some(cast) => (cast.sunrise.clone(), cast.sunset.clone()),
+        None => match client.sun(city, &today) {
+            Ok(sun) => (sun.sunrise, sun.sunset),
+            Err(_) => (None, None),
+        },
+    };
+    if args.period.at(now.time(), &sunrise, &sunset) == Period::Night {
+        live.icon = night_icon(&live.icon).to_owned();
+    }
+
+    let data_day = casts
+        .get(args.day.saturating_sub(1))
+        .or(casts.last())
+        .cloned();
+
+    let mut values = live.values(args);
+    client.sun_values(args, city, &today, &mut values);
+    let air = client.air_values(args, city, &mut values);
+
+    if args.waybar {
+        let mut waybar = waybar(args, LIVE_FORMAT, &values, &live.weather, live.temp_float);
+        waybar.tooltip = Some(match (&args.tooltip_format, &data_day) {
+            (Some(format), _) => render(format, &values),
+            (None, Some(data_day)) => {
+                format!(
+                    "{}\n\n{}",
+                    live.tooltip(args),
+                    tooltip(data_day, &casts, args)
+                )
+            }
+            (None, None) => live.tooltip(args),
+        });
+        client.print_waybar(args, city, waybar, air);
+    } else if let Some(format) = &args.format {
+        println!("{}", render(format, &values));
     } else {
-        let re = serde_json::to_string(&data).unwrap();
+        let re = match data_day {
+            Some(data_day) => serde_json::to_string(&serde_json::json!({
+                "live": live,
+                "forecast": Weather::new(data_day, args.period, now),
+            })),
+            None => serde_json::to_string(&live),
+        }
+        .unwrap();
         println!("{}", re);
     }
 
     Ok(())
 }
+
+/// 输出 `城市参数 名称 地区`
+fn search(client: &Client, name: &str) -> Result<()> {
+    let places = client.provider.search(name)?;
+
+    if places.is_empty() {
+        return Error::WeatherFailError.into();
+    }
+
+    for place in places {
+        println!("{}\t{}\t{}", place.city, place.name, place.region);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    /// 与 `wayinfo weather` 相同，子命令不检查 `--city`
+    #[derive(Parser)]
+    #[command(subcommand_negates_reqs(true))]
+    struct Cli {
+        #[command(flatten)]
+        weather: WeatherArgs,
+    }
+
+    fn city(argv: &[&str]) -> Result<String> {
+        let cli = Cli::try_parse_from(["weather", "--provider", "wttr"].iter().chain(argv))?;
+        Client::new(&cli.weather)?.city(&cli.weather)
+    }
+
+    #[test]
+    fn city_required() {
+        for command in ["aqi", "sun", "alerts"] {
+            let err = city(&[command]).unwrap_err();
+            assert!(
+                err.to_string().contains("--auto-locate"),
+                "{command}: {err}"
+            );
+        }
+        assert_eq!(
+            city(&["--city", " 31.23,121.47 ", "sun"]).unwrap(),
+            "31.23,121.47"
+        );
+        assert!(city(&[]).is_err());
+    }
+
+    #[test]
+    fn open_meteo_url() {
+        let client = |provider: &str| {
+            let argv = [
+                "weather",
+                "--city",
+                "上海",
+                "--key",
+                "k",
+                "--base-url",
+                "http://127.0.0.1:1",
+            ];
+            let cli = Cli::try_parse_from(argv.iter().chain(&["--provider", provider])).unwrap();
+            Client::new(&cli.weather).unwrap()
+        };
+        assert_eq!(
+            client("open-meteo").open_meteo_url().as_deref(),
+            Some("http://127.0.0.1:1")
+        );
+        for provider in ["amap", "owm", "wttr"] {
+            assert_eq!(client(provider).open_meteo_url(), None, "{provider}");
+        }
+    }
+
+    /// 日出日落为城市时间，与城市当前时间比较
+    #[test]
+    fn period_in_city_time() {
+        let cast: Forecast = serde_json::from_value(serde_json::json!({
+            "date": "2024-06-21", "week": "5",
+            "dayweather": "晴", "daytemp": "30", "daywind": "东", "daypower": "3",
+            "nightweather": "晴", "nighttemp": "22", "nightwind": "东", "nightpower": "3",
+            "daytemp_float": "30.0", "nighttemp_float": "22.0",
+            "sunrise": "04:50", "sunset": "19:01"
+        }))
+        .unwrap();
+        let at = |time: &str| {
+            NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M")
+                .map(|now| Period::Auto.resolve(&cast, now))
+                .unwrap()
+        };
+
+        assert!(at("2024-06-21 04:49") == Period::Night);
+        assert!(at("2024-06-21 12:00") == Period::Day);
+        assert!(at("2024-06-21 19:01") == Period::Night);
+        // 预报不是城市的今天
+        assert!(at("2024-06-20 23:00") == Period::Day);
+        assert!(Period::Night.resolve(&cast, NaiveDateTime::default()) == Period::Night);
+    }
+
+    #[test]
+    fn forecast_values() {
+        let cli = Cli::try_parse_from(["weather", "--city", "上海"]).unwrap();
+        let weather = Weather {
+            date: "2024-05-01".to_owned(),
+            week: "3".to_owned(),
+            period: Period::Day,
+            icon: "󰖙".to_owned(),
+            weather: "晴".to_owned(),
+            temp: "24".to_owned(),
+            temp_float: 24.0,
+            wind: "东南".to_owned(),
+            power: "3".to_owned(),
+            temp_min: 17.0,
+            temp_max: 24.0,
+            sunrise: None,
+            sunset: None,
+        };
+        let values = weather.values(&cli.weather, "上海");
+        assert_eq!(
+            render("{temp_min}~{temp_max} {feels_like} {humidity}%", &values),
+            "17~24 {feels_like} {humidity}%"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+
+use super::{endpoint, get_json, Client, Values, WeatherArgs};
+use crate::{errors::Error, waybar::WaybarData, Result};
+
+const HOST: &str = "https://air-quality-api.open-meteo.com";
+
+/// 空气质量指数分级上限
+const IAQI: [f64; 8] = [0.0, 50.0, 100.0, 150.0, 200.0, 300.0, 400.0, 500.0];
+/// PM2.5 24 小时平均，μg/m³
+const PM2_5: [f64; 8] = [0.0, 35.0, 75.0, 115.0, 150.0, 250.0, 350.0, 500.0];
+/// PM10 24 小时平均，μg/m³
+const PM10: [f64; 8] = [0.0, 50.0, 150.0, 250.0, 350.0, 420.0, 500.0, 600.0];
+/// O3 1 小时平均，μg/m³
+const O3: [f64; 8] = [0.0, 160.0, 200.0, 300.0, 400.0, 800.0, 1000.0, 1200.0];
+
+/// 空气质量，AQI 按 HJ 633 计算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct Air {
+    pub aqi: u32,
+    /// 优、良、轻度污染 ..
+    pub category: String,
+    /// 没有监测数据时为空，不参与计算
+    pub pm2_5: Option<f64>,
+    pub pm10: Option<f64>,
+    pub o3: Option<f64>,
+    pub time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Current {
+    time: String,
+    #[serde(default)]
+    pm2_5: Option<f64>,
+    #[serde(default)]
+    pm10: Option<f64>,
+    #[serde(default)]
+    ozone: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AirData {
+    current: Current,
+}
+
+impl Air {
+    /// 缺少的污染物跳过，都没有时失败
+    fn new(current: Current) -> Result<Self> {
+        let (pm2_5, pm10, o3) = (current.pm2_5, current.pm10, current.ozone);
+
+        let aqi = [(pm2_5, &PM2_5), (pm10, &PM10), (o3, &O3)]
+            .into_iter()
+            .filter_map(|(value, limits)| Some(iaqi(value?, limits)))
+            .reduce(f64::max);
+        let Some(aqi) = aqi else {
+            return Error::WeatherFailError.into();
+        };
+        let aqi = aqi.ceil() as u32;
+
+        Ok(Air {
+            aqi,
+            category: level(aqi).0.to_owned(),
+            pm2_5,
+            pm10,
+            o3,
+            time: current.time.replace('T', " "),
+        })
+    }
+
+    /// 如 `aqi-good`
+    pub fn class(&self) -> &'static str {
+        level(self.aqi).1
+    }
+
+    pub fn tooltip(&self, args: &WeatherArgs) -> String {
+        let mut lines = vec![format!("AQI {} {}", self.aqi, args.lang.tr(&self.category))];
+        for (name, value) in [("PM2.5", self.pm2_5), ("PM10", self.pm10), ("O₃", self.o3)] {
+            if let Some(value) = value {
+                lines.push(format!("{name} {value:.0} μg/m³"));
+            }
+        }
+        lines.push(format!("{} {}", args.lang.tr("更新"), self.time));
+        lines.join("\n")
+    }
+
+    /// 添加 AQI class 和 tooltip
+    pub fn mark(&self, args: &WeatherArgs, data: &mut WaybarData) {
+        data.class.push(' ');
+        data.class.push_str(self.class());
+        data.tooltip = Some(match data.tooltip.take() {
+            Some(tooltip) => format!("{tooltip}\n\n{}", self.tooltip(args)),
+            None => self.tooltip(args),
+        });
+    }
+}
+
+/// 分指数，超出最高浓度时为 500
+fn iaqi(value: f64, limits: &[f64; 8]) -> f64 {
+    match limits.windows(2).position(|w| value <= w[1]) {
+        Some(i) => {
+            let (lo, hi) = (limits[i], limits[i + 1]);
+            (IAQI[i + 1] - IAQI[i]) / (hi - lo) * (value - lo) + IAQI[i]
+        }
+        None => IAQI[7],
+    }
+}
+
+/// 类别和 class
+fn level(aqi: u32) -> (&'static str, &'static str) {
+    match aqi {
+        0..=50 => ("优", "aqi-excellent"),
+        51..=100 => ("良", "aqi-good"),
+        101..=150 => ("轻度污染", "aqi-light"),
+        151..=200 => ("中度污染", "aqi-moderate"),
+        201..=300 => ("重度污染", "aqi-heavy"),
+        _ => ("严重污染", "aqi-severe"),
+    }
+}
+
+impl Client {
+    /// open-meteo 空气质量，城市转换为坐标
+    pub(super) fn air(&self, city: &str) -> Result<Air> {
+        self.cache.fetch(&format!("{city}-air"), || {
+            let (lat, lon) = self.coordinates(city)?;
+
+            let url = endpoint(&self.open_meteo_url(), HOST, "/v1/air-quality");
+            let data: AirData = get_json(
+                &url,
+                &[
+                    ("latitude", &lat.to_string()),
+                    ("longitude", &lon.to_string()),
+                    ("current", "pm2_5,pm10,ozone"),
+                    ("timezone", "auto"),
+                ],
+            )?;
+
+            Air::new(data.current)
+        })
+    }
+
+    /// `--aqi` 或模板中有 `{aqi}` 时获取空气质量，添加模板变量
+    pub(super) fn air_values(
+        &self,
+        args: &WeatherArgs,
+        city: &str,
+        values: &mut Values,
+    ) -> Option<Air> {
+        let templates = [&args.format, &args.tooltip_format];
+        let in_template = templates
+            .iter()
+            .any(|t| t.as_deref().is_some_and(|t| t.contains("{aqi")));
+        if !args.aqi && !in_template {
+            return None;
+        }
+
+        let air = match self.air(city) {
+            Ok(air) => air,
+            Err(err) => {
+                log::warn!("Failed to get air quality: {}", err);
+                return None;
+            }
+        };
+
+        values.push(("aqi", air.aqi.to_string()));
+        values.push(("aqi_category", args.lang.tr(&air.category)));
+
+        Some(air)
+    }
+}
+
+pub(super) fn print(args: &WeatherArgs, client: &Client, city: &str) -> Result<()> {
+    let air = client.air(city)?;
+
+    if args.waybar {
+        let data = WaybarData {
+            class: format!("wayinfo-weather-aqi {}", air.class()),
+            text: format!("󰵃 {} {}", air.aqi, args.lang.tr(&air.category)),
+            alt: Some(air.category.clone()),
+            tooltip: Some(air.tooltip(args)),
+            percentage: Some((air.aqi as usize * 100 / 500).min(100)),
+        };
+        let re = serde_json::to_string(&data).unwrap();
+        println!("{}", re);
+    } else {
+        let re = serde_json::to_string(&air).unwrap();
+        println!("{}", re);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current(pm2_5: Option<f64>, pm10: Option<f64>, ozone: Option<f64>) -> Current {
+        Current {
+            time: "2024-05-01T20:00".to_owned(),
+            pm2_5,
+            pm10,
+            ozone,
+        }
+    }
+
+    #[test]
+    fn band_edges() {
+        for (value, limits, expect) in [
+            (0.0, &PM2_5, 0.0),
+            (35.0, &PM2_5, 50.0),
+            (75.0, &PM2_5, 100.0),
+            (115.0, &PM2_5, 150.0),
+            (55.0, &PM2_5, 75.0),
+            (150.0, &PM10, 100.0),
+            (160.0, &O3, 50.0),
+            (200.0, &O3, 100.0),
+            (1300.0, &O3, 500.0),
+        ] {
+            assert_eq!(iaqi(value, limits), expect, "{value}");
+        }
+    }
+
+    #[test]
+    fn levels() {
+        for (aqi, class) in [
+            (0, "aqi-excellent"),
+            (50, "aqi-excellent"),
+            (51, "aqi-good"),
+            (100, "aqi-good"),
+            (101, "aqi-light"),
+            (150, "aqi-light"),
+            (151, "aqi-moderate"),
+            (200, "aqi-moderate"),
+            (201, "aqi-heavy"),
+            (300, "aqi-heavy"),
+            (301, "aqi-severe"),
+        ] {
+            assert_eq!(level(aqi).1, class, "{aqi}");
+        }
+    }
+
+    #[test]
+    fn max_sub_index() {
+        let air = Air::new(current(Some(75.0), Some(40.0), Some(100.0))).unwrap();
+        assert_eq!(air.aqi, 100);
+        assert_eq!(air.category, "良");
+        assert_eq!(air.time, "2024-05-01 20:00");
+    }
+
+    #[test]
+    fn missing_values() {
+        let air = Air::new(current(None, None, Some(200.0))).unwrap();
+        assert_eq!((air.aqi, air.pm2_5), (100, None));
+        assert!(Air::new(current(None, None, None)).is_err());
+    }
+}
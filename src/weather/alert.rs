@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Local};
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+use super::{unsupported, Client, WeatherArgs};
+use crate::{waybar::WaybarData, Result};
+
+/// 气象预警
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct Alert {
+    /// 用于只通知一次
+    pub id: String,
+    /// 预警名称，如 `台风蓝色预警`
+    pub event: String,
+    /// 发布单位
+    pub sender: String,
+    /// 开始和结束时间，秒
+    pub start: i64,
+    pub end: i64,
+    pub description: String,
+}
+
+impl Alert {
+    fn summary(&self) -> String {
+        let time = |t: i64| {
+            DateTime::from_timestamp(t, 0)
+                .map(|t| t.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+                .unwrap_or_default()
+        };
+        format!(
+            "{} {} ~ {} {}",
+            self.event,
+            time(self.start),
+            time(self.end),
+            self.sender
+        )
+    }
+}
+
+impl Client {
+    /// 当前预警，不支持预警的数据源为空
+    pub(super) fn alerts(&self, city: &str) -> Result<Vec<Alert>> {
+        match self
+            .cache
+            .fetch(&format!("{city}-alerts"), || self.provider.alerts(city))
+        {
+            Err(err) if unsupported(err.as_ref()) => {
+                log::info!("{}", err);
+                Ok(Vec::new())
+            }
+            alerts => alerts,
+        }
+    }
+
+    /// 新的预警发送通知，已通知的 id 保存在缓存中
+    pub(super) fn notify_alerts(&self, alerts: &[Alert]) {
+        let seen: HashSet<String> = self.cache.load("alerts-seen").unwrap_or_default();
+
+        for alert in alerts.iter().filter(|a| !seen.contains(&a.id)) {
+            notify(alert);
+        }
+
+        // 只保留当前预警，过期的不再记录
+        let seen: HashSet<&String> = alerts.iter().map(|a| &a.id).collect();
+        self.cache.save("alerts-seen", &seen);
+    }
+
+    /// 添加 `alert` class 和预警内容
+    pub(super) fn mark_alerts(&self, args: &WeatherArgs, city: &str, data: &mut WaybarData) {
+        let alerts = match self.alerts(city) {
+            Ok(alerts) => alerts,
+            Err(err) => {
+                log::warn!("Failed to get weather alerts: {}", err);
+                return;
+            }
+        };
+
+        if args.notify {
+            self.notify_alerts(&alerts);
+        }
+
+        if alerts.is_empty() {
+            return;
+        }
+
+        data.class.push_str(" alert");
+        let lines: Vec<String> = alerts
+            .iter()
+            .map(|a| format!("󰀦 {}", a.summary()))
+            .collect();
+        data.tooltip = Some(match data.tooltip.take() {
+            Some(tooltip) => format!("{}\n\n{tooltip}", lines.join("\n")),
+            None => lines.join("\n"),
+        });
+    }
+}
+
+/// 输出预警，waybar 文本为第一条预警名称
+pub(super) fn print(args: &WeatherArgs, client: &Client, city: &str) -> Result<()> {
+    let alerts = client.alerts(city)?;
+
+    if args.notify {
+        client.notify_alerts(&alerts);
+    }
+
+    if args.waybar {
+        let mut data = WaybarData {
+            class: "wayinfo-weather-alerts".to_owned(),
+            text: alerts.first().map(|a| a.event.clone()).unwrap_or_default(),
+            alt: Some(alerts.len().to_string()),
+            tooltip: None,
+            percentage: None,
+        };
+        if !alerts.is_empty() {
+            data.class.push_str(" alert");
+            let lines: Vec<String> = alerts
+                .iter()
+                .map(|a| format!("{}\n{}", a.summary(), a.description.trim()))
+                .collect();
+            data.tooltip = Some(lines.join("\n\n"));
+        }
+        let re = serde_json::to_string(&data).unwrap();
+        println!("{}", re);
+    } else {
+        let re = serde_json::to_string(&alerts).unwrap();
+        println!("{}", re);
+    }
+
+    Ok(())
+}
+
+fn notify(alert: &Alert) {
+    let result = Notification::new()
+        .appname("wayinfo")
+        .summary(&alert.event)
+        .body(&format!(
+            "{}\n{}",
+            alert.summary(),
+            alert.description.trim()
+        ))
+        .show();
+
+    if let Err(e) = result {
+        log::warn!("Failed to send notification: {}", e);
+    }
+}
@@ -0,0 +1,306 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{endpoint, geo, geo::Place, get_json, night_icon, Forecast, Live, Provider};
+use crate::{errors::Error, Result};
+
+const HOST: &str = "https://restapi.amap.com";
+
+/// 高德天气，城市可以是名称或 adcode
+pub(super) struct Amap {
+    pub key: String,
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Forecasts {
+    city: String,
+    adcode: String,
+    province: String,
+    reporttime: String,
+    casts: Vec<Forecast>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Lives {
+    city: String,
+    adcode: String,
+    weather: String,
+    temperature: String,
+    winddirection: String,
+    windpower: String,
+    humidity: String,
+    reporttime: String,
+    #[serde(default)]
+    temperature_float: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WeatherData {
+    status: String,
+    count: String,
+    info: String,
+    infocode: String,
+    #[serde(default)]
+    forecasts: Vec<Forecasts>,
+    #[serde(default)]
+    lives: Vec<Lives>,
+}
+
+#[derive(Debug, Deserialize)]
+struct District {
+    adcode: String,
+    name: String,
+    /// `经度,纬度`
+    #[serde(default)]
+    center: String,
+    /// province, city, district
+    level: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DistrictData {
+    status: String,
+    #[serde(default)]
+    districts: Vec<District>,
+}
+
+impl Amap {
+    /// `extensions` 为 `base` 实况天气，`all` 预报天气
+    fn weather_info(&self, city: &str, extensions: &str) -> Result<WeatherData> {
+        let url = endpoint(&self.base_url, HOST, "/v3/weather/weatherInfo");
+        let data: WeatherData = get_json(
+            &url,
+            &[
+                ("key", &self.key),
+                ("city", city),
+                ("extensions", extensions),
+                ("output", "json"),
+            ],
+        )?;
+
+        if data.status != "1" {
+            return Error::WeatherFailError.into();
+        }
+
+        Ok(data)
+    }
+}
+
+impl Provider for Amap {
+    fn forecast(&self, city: &str) -> Result<Vec<Forecast>> {
+        let data = self.weather_info(city, "all")?;
+        if data.forecasts.is_empty() {
+            return Error::WeatherFailError.into();
+        }
+
+        let casts = data
+            .forecasts
+            .into_iter()
+            .flat_map(|f| f.casts)
+            .map(|mut cast| {
+                cast.day_icon = weather_icon(&cast.day_weather).to_owned();
+                cast.night_icon = night_icon(weather_icon(&cast.night_weather)).to_owned();
+                cast
+            })
+            .collect();
+
+        Ok(casts)
+    }
+
+    fn live(&self, city: &str) -> Result<Live> {
+        let data = self.weather_info(city, "base")?;
+        let Some(live) = data.lives.into_iter().next() else {
+            return Error::WeatherFailError.into();
+        };
+
+        let temp_float = live
+            .temperature_float
+            .as_deref()
+            .unwrap_or(&live.temperature)
+            .parse()
+            .unwrap_or_default();
+
+        Ok(Live {
+            city: live.city,
+            icon: weather_icon(&live.weather).to_owned(),
+            weather: live.weather,
+            temp: live.temperature,
+            temp_float,
+            feels_like: None,
+            humidity: live.humidity,
+            wind: live.winddirection,
+            power: live.windpower,
+            report_time: live.reporttime,
+        })
+    }
+
+    fn search(&self, name: &str) -> Result<Vec<Place>> {
+        let url = endpoint(&self.base_url, HOST, "/v3/config/district");
+        let data: DistrictData = get_json(
+            &url,
+            &[
+                ("key", &self.key),
+                ("keywords", name),
+                ("subdistrict", "0"),
+                ("output", "json"),
+            ],
+        )?;
+
+        if data.status != "1" {
+            return Error::WeatherFailError.into();
+        }
+
+        let places = data
+            .districts
+            .into_iter()
+            .map(|d| Place {
+                coordinates: geo::coordinates(&d.center).map(|(lon, lat)| (lat, lon)),
+                name: d.name,
+                region: d.level,
+                city: d.adcode,
+            })
+            .collect();
+
+        Ok(places)
+    }
+
+    /// 未指定接口时使用高德 IP 定位，返回 adcode，
+    /// 否则使用接口返回的城市名称
+    fn locate(&self, url: Option<&str>) -> Result<String> {
+        if let Some(url) = url {
+            return match geo::locate_ip(url)?.city {
+                Some(city) => Ok(city),
+                None => Error::WeatherFailError.into(),
+            };
+        }
+
+        let url = endpoint(&self.base_url, HOST, "/v3/ip");
+        let data: Value = get_json(&url, &[("key", &self.key), ("output", "json")])?;
+
+        // 定位失败时字段为空数组
+        match data.get("adcode").and_then(Value::as_str) {
+            Some(adcode) if !adcode.is_empty() => Ok(adcode.to_owned()),
+            _ => Error::WeatherFailError.into(),
+        }
+    }
+}
+
+fn weather_icon(s: &str) -> &str {
+    match s {
+        "晴" => "󰖙",
+        "少云" => "󰖐",
+        "晴间多云" => "󰖕",
+        "多云" => "󰼯",
+        "阴" => "󰼰",
+        "有风" | "平静" | "微风" | "和风" | "清风" => "",
+        "强风/劲风" | "疾风" | "大风" => "󰖝",
+        "烈风" | "风暴" | "狂爆风" => "󰼸",
+        "飓风" | "热带风暴" | "龙卷风" => "󰢘",
+        "霾" | "中度霾" | "重度霾" | "严重霾" => "󰖑",
+        "阵雨" => "󰖓",
+        "雷阵雨" | "雷阵雨并伴有冰雹" => "󰙾",
+        "毛毛雨/细雨" | "雨" | "小雨" => "󰖒",
+        "中雨" | "大雨" | "小雨-中雨" | "中雨-大雨" | "大雨-暴雨" => "󰖗",
+        "暴雨"
+        | "大暴雨"
+        | "特大暴雨"
+        | "强阵雨"
+        | "强雷阵雨"
+        | "极端降雨"
+        | "暴雨-大暴雨"
+        | "大暴雨-特大暴雨" => "󰖖",
+        "雨雪天气" | "雨夹雪" | "阵雨夹雪" | "冻雨" => "󰙿",
+        "阵雪" => "󰼴",
+        "雪" | "小雪" | "中雪" | "小雪-中雪" => "󰖘",
+        "大雪" | "暴雪" | "中雪-大雪" | "大雪-暴雪" => "󰼶",
+        "浮尘" | "扬沙" | "沙尘暴" | "强沙尘暴" => "",
+        "雾" | "浓雾" | "强浓雾" | "轻雾" | "大雾" | "特强浓雾" => "󰖑",
+
+        "热" => "󰖙 ",
+        "冷" => "",
+
+        // "未知"=>"未知",
+        _ => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::{condition::Condition, mock};
+
+    const FORECAST: &str = r#"{"status": "1", "count": "1", "info": "OK", "infocode": "10000",
+        "forecasts": [{"city": "浦东新区", "adcode": "310115", "province": "上海",
+            "reporttime": "2024-05-01 11:02:44",
+            "casts": [
+                {"date": "2024-05-01", "week": "3", "dayweather": "中雨", "nightweather": "多云",
+                 "daytemp": "24", "nighttemp": "17", "daywind": "东", "nightwind": "东",
+                 "daypower": "1-3", "nightpower": "1-3",
+                 "daytemp_float": "24.0", "nighttemp_float": "17.0"},
+                {"date": "2024-05-02", "week": "4", "dayweather": "晴", "nightweather": "晴",
+                 "daytemp": "28", "nighttemp": "18", "daywind": "南", "nightwind": "南",
+                 "daypower": "≤3", "nightpower": "≤3",
+                 "daytemp_float": "28.0", "nighttemp_float": "18.0"}
+            ]}]}"#;
+
+    /// 没有 `temperature_float`
+    const LIVE: &str = r#"{"status": "1", "count": "1", "info": "OK", "infocode": "10000",
+        "lives": [{"province": "上海", "city": "浦东新区", "adcode": "310115", "weather": "雷阵雨",
+            "temperature": "22", "winddirection": "东南", "windpower": "≤3", "humidity": "85",
+            "reporttime": "2024-05-01 20:00:00"}]}"#;
+
+    const INVALID_KEY: &str =
+        r#"{"status": "0", "count": "0", "info": "INVALID_USER_KEY", "infocode": "10001"}"#;
+
+    fn provider() -> Amap {
+        let base_url = mock::serve(&[
+            ("city=000000", INVALID_KEY),
+            ("extensions=all", FORECAST),
+            ("extensions=base", LIVE),
+        ]);
+        Amap {
+            key: "test".to_owned(),
+            base_url: Some(base_url),
+        }
+    }
+
+    #[test]
+    fn forecast() {
+        let casts = provider().forecast("310115").unwrap();
+        assert_eq!(casts.len(), 2);
+
+        let today = &casts[0];
+        assert_eq!(today.day_weather, "中雨");
+        assert_eq!(today.day_icon, "󰖗");
+        assert_eq!(today.day_temp_float, 24.0);
+        assert_eq!(Condition::of(&today.day_weather), Some(Condition::Rain));
+        assert_eq!(today.night_icon, "󰼯");
+        assert_eq!(Condition::of(&today.night_weather), Some(Condition::Cloudy));
+        // 高德没有日出日落
+        assert_eq!(today.sunrise, None);
+
+        assert_eq!(casts[1].night_icon, "󰖔");
+    }
+
+    #[test]
+    fn live() {
+        let live = provider().live("310115").unwrap();
+        assert_eq!(live.city, "浦东新区");
+        assert_eq!(live.icon, "󰙾");
+        assert_eq!(Condition::of(&live.weather), Some(Condition::Storm));
+        // 没有小数温度时使用整数温度
+        assert_eq!(live.temp_float, 22.0);
+        assert_eq!(live.feels_like, None);
+        assert_eq!(live.humidity, "85");
+    }
+
+    #[test]
+    fn failed_status() {
+        let err = provider().forecast("000000").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::WeatherFailError)
+        ));
+    }
+}
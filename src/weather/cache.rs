@@ -0,0 +1,292 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    env, fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::locale::Lang;
+use crate::{errors::Error, Result};
+
+/// 请求结果缓存，位于 `$XDG_CACHE_HOME/wayinfo`
+pub(super) struct Cache {
+    /// 数据源
+    prefix: String,
+    ttl: Duration,
+    retries: u32,
+    /// 天气数据使用过期缓存时，缓存的秒数
+    stale: Cell<Option<u64>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    /// 秒
+    time: u64,
+    data: T,
+}
+
+impl Cache {
+    pub fn new(provider: &str, ttl: Duration, retries: u32) -> Self {
+        Cache {
+            prefix: format!("weather-{provider}"),
+            ttl,
+            retries,
+            stale: Cell::new(None),
+        }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        let name = name.replace(['/', '\\'], "_");
+        cache_dir().join(format!("{}-{name}.json", self.prefix))
+    }
+
+    /// 读取保存的数据
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        fs::read_to_string(self.path(name))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+    }
+
+    pub fn save<T: Serialize>(&self, name: &str, data: &T) {
+        let path = self.path(name);
+        let saved = fs::create_dir_all(cache_dir())
+            .and_then(|_| fs::write(&path, serde_json::to_string(data).unwrap()));
+        if let Err(e) = saved {
+            log::warn!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+
+    /// 缓存未过期时直接使用，否则请求，失败时使用过期缓存
+    pub fn fetch<T, F>(&self, name: &str, fetch: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Fn() -> Result<T>,
+    {
+        self.fetch_entry(name, fetch).map(|(data, _)| data)
+    }
+
+    /// 同 `fetch`，用于预报和实况，使用过期缓存时记录缓存时间
+    pub fn fetch_marked<T, F>(&self, name: &str, fetch: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Fn() -> Result<T>,
+    {
+        let (data, age) = self.fetch_entry(name, fetch)?;
+        if let Some(age) = age {
+            self.stale
+                .set(Some(self.stale.get().unwrap_or_default().max(age)));
+        }
+        Ok(data)
+    }
+
+    /// 数据和使用过期缓存时的缓存秒数
+    fn fetch_entry<T, F>(&self, name: &str, fetch: F) -> Result<(T, Option<u64>)>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Fn() -> Result<T>,
+    {
+        let cached: Option<Entry<T>> = self.load(name);
+
+        let now = now_secs();
+        let cached = match cached {
+            Some(entry) if now.saturating_sub(entry.time) < self.ttl.as_secs() => {
+                return Ok((entry.data, None))
+            }
+            cached => cached,
+        };
+
+        match retry(self.retries, fetch) {
+            Ok(data) => {
+                let entry = Entry { time: now, data };
+                self.save(name, &entry);
+                Ok((entry.data, None))
+            }
+            Err(err) => match cached {
+                Some(entry) => {
+                    log::warn!("Weather failed, use cache {}: {}", name, err);
+                    Ok((entry.data, Some(now.saturating_sub(entry.time))))
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    /// 城市名称对应的 adcode 或坐标，查询结果长期保存，
+    /// `resolve` 返回 `None` 时直接使用名称
+    pub fn city<F>(&self, name: &str, resolve: F) -> Result<String>
+    where
+        F: FnOnce() -> Result<Option<String>>,
+    {
+        let mut cities: HashMap<String, String> = self.load("cities").unwrap_or_default();
+
+        if let Some(city) = cities.get(name) {
+            return Ok(city.clone());
+        }
+
+        let Some(city) = resolve()? else {
+            return Ok(name.to_owned());
+        };
+        cities.insert(name.to_owned(), city.clone());
+        self.save("cities", &cities);
+
+        Ok(city)
+    }
+
+    /// 预报或实况使用了过期缓存时，缓存的秒数
+    pub fn stale(&self) -> Option<u64> {
+        self.stale.get()
+    }
+}
+
+/// 网络错误和服务端错误时重试，间隔每次翻倍
+fn retry<T>(retries: u32, fetch: impl Fn() -> Result<T>) -> Result<T> {
+    let mut delay = Duration::from_millis(500);
+    let mut attempt = 0;
+
+    loop {
+        match fetch() {
+            Err(err) if attempt < retries && transient(err.as_ref()) => {
+                log::warn!("Weather failed, retry in {:?}: {}", delay, err);
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn transient(err: &(dyn std::error::Error + 'static)) -> bool {
+    if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+        return err.is_timeout() || err.is_connect() || err.is_request();
+    }
+
+    match err.downcast_ref::<Error>() {
+        Some(Error::Reqwest(err)) => err.is_timeout() || err.is_connect() || err.is_request(),
+        Some(Error::WeatherResponseError { code }) => {
+            code.is_server_error() || *code == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => false,
+    }
+}
+
+/// 缓存时间，如 `5分钟` 或 `5m`
+pub(super) fn age_text(secs: u64, lang: Lang) -> String {
+    let (value, unit) = match secs {
+        0..=59 => (secs, ("秒", "s")),
+        60..=3599 => (secs / 60, ("分钟", "m")),
+        3600..=86399 => (secs / 3600, ("小时", "h")),
+        _ => (secs / 86400, ("天", "d")),
+    };
+
+    match lang {
+        Lang::Zh => format!("{value}{}", unit.0),
+        Lang::En => format!("{value}{}", unit.1),
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(env::temp_dir)
+        .join("wayinfo")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, sync::Once};
+
+    use super::*;
+
+    /// 所有测试共用临时的 `XDG_CACHE_HOME`，每个测试使用不同的数据源名称
+    fn cache(provider: &str, ttl: u64, retries: u32) -> Cache {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let dir = tempfile::tempdir().unwrap().into_path();
+            env::set_var("XDG_CACHE_HOME", dir);
+        });
+        Cache::new(provider, Duration::from_secs(ttl), retries)
+    }
+
+    fn failed() -> Result<u32> {
+        Error::WeatherFailError.into()
+    }
+
+    /// 保存一小时前的缓存
+    fn save_old(cache: &Cache, name: &str, data: u32) {
+        let time = now_secs() - 3600;
+        cache.save(name, &Entry { time, data });
+    }
+
+    #[test]
+    fn ttl_hit() {
+        let cache = cache("test-ttl", 600, 0);
+        assert_eq!(cache.fetch("a", || Ok(1)).unwrap(), 1);
+        // 未过期时不请求
+        assert_eq!(cache.fetch("a", failed).unwrap(), 1);
+        assert_eq!(cache.stale(), None);
+
+        save_old(&cache, "a", 2);
+        assert_eq!(cache.fetch("a", || Ok(3)).unwrap(), 3);
+    }
+
+    #[test]
+    fn stale_fallback() {
+        let cache = cache("test-stale", 600, 0);
+        assert!(cache.fetch("missing", failed).is_err());
+
+        save_old(&cache, "air", 1);
+        assert_eq!(cache.fetch("air", failed).unwrap(), 1);
+        assert_eq!(cache.stale(), None, "only weather data marks stale");
+
+        save_old(&cache, "forecast", 2);
+        assert_eq!(cache.fetch_marked("forecast", failed).unwrap(), 2);
+        assert!(cache.stale().is_some_and(|age| (3600..3660).contains(&age)));
+    }
+
+    #[test]
+    fn retries() {
+        let calls = &Cell::new(0);
+        let fetch = |code: StatusCode| {
+            move || -> Result<u32> {
+                calls.set(calls.get() + 1);
+                Error::WeatherResponseError { code }.into()
+            }
+        };
+
+        assert!(retry(1, fetch(StatusCode::INTERNAL_SERVER_ERROR)).is_err());
+        assert_eq!(calls.replace(0), 2);
+        assert!(retry(1, fetch(StatusCode::NOT_FOUND)).is_err());
+        assert_eq!(calls.replace(0), 1);
+    }
+
+    #[test]
+    fn transient_errors() {
+        let response = |code| Error::WeatherResponseError { code };
+        assert!(transient(&response(StatusCode::BAD_GATEWAY)));
+        assert!(transient(&response(StatusCode::TOO_MANY_REQUESTS)));
+        assert!(!transient(&response(StatusCode::UNAUTHORIZED)));
+        assert!(!transient(&Error::WeatherFailError));
+    }
+
+    #[test]
+    fn age() {
+        assert_eq!(age_text(59, Lang::Zh), "59秒");
+        assert_eq!(age_text(60, Lang::Zh), "1分钟");
+        assert_eq!(age_text(7200, Lang::En), "2h");
+        assert_eq!(age_text(86400 * 3, Lang::En), "3d");
+    }
+}
@@ -0,0 +1,97 @@
+/// 天气状况，作为 waybar class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Condition {
+    Sunny,
+    Cloudy,
+    Rain,
+    HeavyRain,
+    Snow,
+    Fog,
+    Haze,
+    Storm,
+    Dust,
+}
+
+/// 关键字按顺序匹配，先匹配更具体的状况。
+/// 各数据源都转换为中文描述，wttr.in 缺少中文时为英文。
+const CONDITIONS: &[(&str, Condition)] = &[
+    ("雷", Condition::Storm),
+    ("冰雹", Condition::Storm),
+    ("thunder", Condition::Storm),
+    ("风暴", Condition::Storm),
+    ("飓风", Condition::Storm),
+    ("龙卷风", Condition::Storm),
+    ("暴雨", Condition::HeavyRain),
+    ("大雨", Condition::HeavyRain),
+    ("强阵雨", Condition::HeavyRain),
+    ("极端降雨", Condition::HeavyRain),
+    ("heavy rain", Condition::HeavyRain),
+    ("torrential", Condition::HeavyRain),
+    ("雪", Condition::Snow),
+    ("snow", Condition::Snow),
+    ("sleet", Condition::Snow),
+    ("blizzard", Condition::Snow),
+    ("ice", Condition::Snow),
+    ("雨", Condition::Rain),
+    ("rain", Condition::Rain),
+    ("drizzle", Condition::Rain),
+    ("shower", Condition::Rain),
+    ("霾", Condition::Haze),
+    ("烟", Condition::Haze),
+    ("haze", Condition::Haze),
+    ("smoke", Condition::Haze),
+    ("雾", Condition::Fog),
+    ("fog", Condition::Fog),
+    ("mist", Condition::Fog),
+    ("沙", Condition::Dust),
+    ("尘", Condition::Dust),
+    ("dust", Condition::Dust),
+    ("sand", Condition::Dust),
+    ("阴", Condition::Cloudy),
+    ("云", Condition::Cloudy),
+    ("cloud", Condition::Cloudy),
+    ("overcast", Condition::Cloudy),
+    ("晴", Condition::Sunny),
+    ("sunny", Condition::Sunny),
+    ("clear", Condition::Sunny),
+];
+
+impl Condition {
+    /// 根据天气描述判断，无法识别时为 `None`
+    pub fn of(weather: &str) -> Option<Condition> {
+        let weather = weather.to_lowercase();
+        CONDITIONS
+            .iter()
+            .find(|(keyword, _)| weather.contains(keyword))
+            .map(|(_, condition)| *condition)
+    }
+
+    /// 英文名称，用于翻译不在词表中的天气
+    pub fn name(&self) -> &'static str {
+        match self {
+            Condition::Sunny => "Sunny",
+            Condition::Cloudy => "Cloudy",
+            Condition::Rain => "Rain",
+            Condition::HeavyRain => "Heavy rain",
+            Condition::Snow => "Snow",
+            Condition::Fog => "Fog",
+            Condition::Haze => "Haze",
+            Condition::Storm => "Thunderstorm",
+            Condition::Dust => "Dust",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Condition::Sunny => "sunny",
+            Condition::Cloudy => "cloudy",
+            Condition::Rain => "rain",
+            Condition::HeavyRain => "heavy-rain",
+            Condition::Snow => "snow",
+            Condition::Fog => "fog",
+            Condition::Haze => "haze",
+            Condition::Storm => "storm",
+            Condition::Dust => "dust",
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::get_json;
+use crate::{errors::Error, Result};
+
+/// 默认 IP 定位接口
+pub(super) const LOCATE_URL: &str = "https://ipinfo.io/json";
+
+/// 城市查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct Place {
+    pub name: String,
+    /// 省份、国家等
+    pub region: String,
+    /// 用于 `--city`，adcode 或 `纬度,经度`
+    pub city: String,
+    /// 纬度,经度
+    #[serde(skip)]
+    pub coordinates: Option<(f64, f64)>,
+}
+
+/// IP 定位结果
+#[derive(Debug, Clone)]
+pub(super) struct Location {
+    pub city: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Location {
+    pub fn coordinates(&self) -> String {
+        format!("{:.4},{:.4}", self.lat, self.lon)
+    }
+}
+
+/// adcode 或 `纬度,经度`，不需要再查询
+pub(super) fn is_resolved(city: &str) -> bool {
+    let adcode = !city.is_empty() && city.chars().all(|c| c.is_ascii_digit());
+    adcode || coordinates(city).is_some()
+}
+
+/// 解析 `纬度,经度`
+pub(super) fn coordinates(city: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = city.split_once(',')?;
+    Some((lat.trim().parse().ok()?, lon.trim().parse().ok()?))
+}
+
+/// 支持 ip-api.com 的 `lat`/`lon`，ipapi.co 的 `latitude`/`longitude` 和 ipinfo.io 的 `loc`
+pub(super) fn locate_ip(url: &str) -> Result<Location> {
+    let data: Value = get_json(url, &[])?;
+
+    let number = |keys: &[&str]| keys.iter().find_map(|k| data.get(k)?.as_f64());
+    let (lat, lon) = match (number(&["lat", "latitude"]), number(&["lon", "longitude"])) {
+        (Some(lat), Some(lon)) => (lat, lon),
+        _ => match data
+            .get("loc")
+            .and_then(Value::as_str)
+            .and_then(coordinates)
+        {
+            Some(loc) => loc,
+            None => return Error::WeatherFailError.into(),
+        },
+    };
+
+    Ok(Location {
+        city: data.get("city").and_then(Value::as_str).map(str::to_owned),
+        lat,
+        lon,
+    })
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, env, fs, path::Path, time::Duration};
+
+use dbus::{
+    arg::{RefArg, Variant},
+    blocking::Connection,
+};
+
+use super::WeatherArgs;
+use crate::{
+    config::{self, Config},
+    errors::Error,
+    Result,
+};
+
+const SECRETS: &str = "org.freedesktop.secrets";
+const SECRETS_PATH: &str = "/org/freedesktop/secrets";
+const SERVICE: &str = "org.freedesktop.Secret.Service";
+
+/// 会话、参数、密钥、类型
+type Secret = (dbus::Path<'static>, Vec<u8>, Vec<u8>, String);
+
+/// 依次查找密钥：`--key`、`--key-file`、环境变量 `WAYINFO_<PROVIDER>_KEY`、
+/// 配置文件、Secret Service，都没有时列出查找过的位置
+pub(super) fn api_key(args: &WeatherArgs) -> Result<String> {
+    let name = args.provider.name();
+    let mut tried = Vec::new();
+
+    if let Some(key) = args.key.as_deref().and_then(non_empty) {
+        return Ok(key);
+    }
+    tried.push("--key".to_owned());
+
+    match &args.key_file {
+        Some(file) => {
+            if let Some(key) = try_key_file("--key-file", Path::new(file), &mut tried) {
+                return Ok(key);
+            }
+        }
+        None => tried.push("--key-file".to_owned()),
+    }
+
+    let var = format!("WAYINFO_{}_KEY", name.to_uppercase().replace('-', "_"));
+    if let Some(key) = env::var(&var).ok().as_deref().and_then(non_empty) {
+        return Ok(key);
+    }
+    tried.push(var);
+
+    let config = Config::load()?;
+    if let Some(cfg) = config.weather.get(&name) {
+        if let Some(key) = cfg.key.as_deref().and_then(non_empty) {
+            return Ok(key);
+        }
+        if let Some(file) = &cfg.key_file {
+            let path = config::expand_home(file);
+            if let Some(key) = try_key_file("key-file", &path, &mut tried) {
+                return Ok(key);
+            }
+        }
+    }
+    tried.push(config::path().display().to_string());
+
+    match secret(&name) {
+        Ok(Some(key)) => return Ok(key),
+        Ok(None) => {}
+        Err(err) => log::info!("Secret Service unavailable: {}", err),
+    }
+    tried.push("secret service".to_owned());
+
+    Error::WeatherKeyError {
+        tried: tried.join(", "),
+    }
+    .into()
+}
+
+fn non_empty(key: &str) -> Option<String> {
+    let key = key.trim();
+    (!key.is_empty()).then(|| key.to_owned())
+}
+
+/// 记录到 `tried`，读取失败时同时记录原因，继续查找下一个位置
+fn try_key_file(source: &str, path: &Path, tried: &mut Vec<String>) -> Option<String> {
+    match read_key_file(path) {
+        Ok(Some(key)) => return Some(key),
+        Ok(None) => tried.push(format!("{source} {}", path.display())),
+        Err(err) => tried.push(format!("{source} {} ({err})", path.display())),
+    }
+    None
+}
+
+/// 去掉首尾空白，其他用户可读时警告
+fn read_key_file(path: &Path) -> Result<Option<String>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if fs::metadata(path)?.permissions().mode() & 0o004 != 0 {
+            log::warn!(
+                "Key file {} is world-readable, run `chmod 600` on it",
+                path.display()
+            );
+        }
+    }
+
+    Ok(non_empty(&fs::read_to_string(path)?))
+}
+
+/// 查找属性 `service=wayinfo provider=<provider>` 的密钥，如
+/// `secret-tool store --label=wayinfo service wayinfo provider amap`。
+/// 只读取已解锁的条目
+fn secret(provider: &str) -> Result<Option<String>> {
+    let conn = Connection::new_session()?;
+    let timeout = Duration::from_secs(2);
+    let service = conn.with_proxy(SECRETS, SECRETS_PATH, timeout);
+
+    let attributes = HashMap::from([("service", "wayinfo"), ("provider", provider)]);
+    let (unlocked, _locked): (Vec<dbus::Path>, Vec<dbus::Path>) =
+        service.method_call(SERVICE, "SearchItems", (attributes,))?;
+    let Some(item) = unlocked.first() else {
+        return Ok(None);
+    };
+
+    // plain 会话，密钥不加密地经过 session bus
+    let (_output, session): (Variant<Box<dyn RefArg>>, dbus::Path) =
+        service.method_call(SERVICE, "OpenSession", ("plain", Variant("")))?;
+
+    let result: std::result::Result<(Secret,), dbus::Error> = conn
+        .with_proxy(SECRETS, item, timeout)
+        .method_call("org.freedesktop.Secret.Item", "GetSecret", (&session,));
+
+    let _: std::result::Result<(), dbus::Error> = conn
+        .with_proxy(SECRETS, &session, timeout)
+        .method_call("org.freedesktop.Secret.Session", "Close", ());
+
+    let ((_, _, value, _),) = result?;
+    Ok(non_empty(&String::from_utf8_lossy(&value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        weather: WeatherArgs,
+    }
+
+    fn key(extra: &[&str]) -> Result<String> {
+        let argv = ["weather", "--city", "上海", "--provider", "owm"];
+        let cli = Cli::try_parse_from(argv.iter().chain(extra))?;
+        api_key(&cli.weather)
+    }
+
+    /// 环境变量是进程共享的，查找顺序在一个测试中依次检查
+    #[test]
+    fn lookup_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("owm.txt");
+        let config = dir.path().join("wayinfo");
+        fs::create_dir(&config).unwrap();
+        fs::write(&file, "\n  file-key \n").unwrap();
+        fs::write(
+            config.join("config.toml"),
+            format!("[weather.owm]\nkey-file = \"{}\"\n", file.display()),
+        )
+        .unwrap();
+
+        env::set_var("XDG_CONFIG_HOME", dir.path());
+        env::set_var("DBUS_SESSION_BUS_ADDRESS", "unix:path=/nonexistent");
+        env::set_var("WAYINFO_OWM_KEY", " env-key ");
+
+        let missing = dir.path().join("missing.txt");
+        let missing = missing.to_str().unwrap();
+        let file = file.to_str().unwrap();
+
+        assert_eq!(
+            key(&["--key", " arg-key ", "--key-file", file]).unwrap(),
+            "arg-key"
+        );
+        assert_eq!(
+            key(&["--key", " ", "--key-file", file]).unwrap(),
+            "file-key"
+        );
+        // 读取失败时继续查找
+        assert_eq!(key(&["--key-file", missing]).unwrap(), "env-key");
+
+        env::remove_var("WAYINFO_OWM_KEY");
+        assert_eq!(key(&[]).unwrap(), "file-key");
+
+        fs::write(file, " \n").unwrap();
+        let err = key(&["--key-file", missing]).unwrap_err().to_string();
+        let tried = [
+            "--key, ",
+            "--key-file ",
+            "missing.txt (",
+            "WAYINFO_OWM_KEY, ",
+            "key-file ",
+            "config.toml, secret service",
+        ];
+        let mut rest = err.as_str();
+        for part in tried {
+            let start = rest.find(part).unwrap_or_else(|| panic!("{part} in {err}"));
+            rest = &rest[start + part.len()..];
+        }
+    }
+}
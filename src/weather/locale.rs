@@ -0,0 +1,218 @@
+use clap::ValueEnum;
+
+use super::condition::Condition;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Lang {
+    /// 中文
+    Zh,
+    /// English
+    En,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Units {
+    /// 摄氏度
+    Metric,
+    /// 华氏度
+    Imperial,
+}
+
+impl Units {
+    /// 数据源都为摄氏度
+    pub fn temp(&self, celsius: f64) -> f64 {
+        match self {
+            Units::Metric => celsius,
+            Units::Imperial => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Units::Metric => "󰔄",
+            Units::Imperial => "󰔅",
+        }
+    }
+}
+
+/// 天气、风向和标签的英文，数据源的描述都为中文
+const WORDS: &[(&str, &str)] = &[
+    // 标签
+    ("白天", "Day"),
+    ("夜间", "Night"),
+    ("日出", "Sunrise"),
+    ("日落", "Sunset"),
+    ("昼长", "Day length"),
+    ("月相", "Moon"),
+    ("湿度", "Humidity"),
+    ("体感", "Feels like"),
+    ("发布", "Updated"),
+    ("更新", "Updated"),
+    ("风力", "Force"),
+    ("缓存于", "Cached"),
+    // 月相
+    ("新月", "New moon"),
+    ("娥眉月", "Waxing crescent"),
+    ("上弦月", "First quarter"),
+    ("盈凸月", "Waxing gibbous"),
+    ("满月", "Full moon"),
+    ("亏凸月", "Waning gibbous"),
+    ("下弦月", "Last quarter"),
+    ("残月", "Waning crescent"),
+    // 风向
+    ("北", "N"),
+    ("东北", "NE"),
+    ("东", "E"),
+    ("东南", "SE"),
+    ("南", "S"),
+    ("西南", "SW"),
+    ("西", "W"),
+    ("西北", "NW"),
+    ("无风向", "Calm"),
+    ("无持续风向", "Variable"),
+    ("旋转不定", "Variable"),
+    // 空气质量
+    ("优", "Excellent"),
+    ("良", "Good"),
+    ("轻度污染", "Lightly polluted"),
+    ("中度污染", "Moderately polluted"),
+    ("重度污染", "Heavily polluted"),
+    ("严重污染", "Severely polluted"),
+    // 天气
+    ("晴", "Sunny"),
+    ("少云", "Mostly sunny"),
+    ("晴间多云", "Partly cloudy"),
+    ("局部多云", "Partly cloudy"),
+    ("多云", "Cloudy"),
+    ("阴", "Overcast"),
+    ("有风", "Windy"),
+    ("平静", "Calm"),
+    ("微风", "Light breeze"),
+    ("和风", "Moderate breeze"),
+    ("清风", "Fresh breeze"),
+    ("强风/劲风", "Strong breeze"),
+    ("疾风", "Near gale"),
+    ("大风", "Gale"),
+    ("烈风", "Strong gale"),
+    ("风暴", "Storm"),
+    ("狂爆风", "Violent storm"),
+    ("飓风", "Hurricane"),
+    ("热带风暴", "Tropical storm"),
+    ("龙卷风", "Tornado"),
+    ("霾", "Haze"),
+    ("中度霾", "Moderate haze"),
+    ("重度霾", "Heavy haze"),
+    ("严重霾", "Severe haze"),
+    ("阵雨", "Showers"),
+    ("雷阵雨", "Thunderstorm"),
+    ("雷阵雨并伴有冰雹", "Thunderstorm with hail"),
+    ("毛毛雨/细雨", "Drizzle"),
+    ("毛毛雨", "Drizzle"),
+    ("雨", "Rain"),
+    ("小雨", "Light rain"),
+    ("中雨", "Moderate rain"),
+    ("大雨", "Heavy rain"),
+    ("暴雨", "Rainstorm"),
+    ("大暴雨", "Heavy rainstorm"),
+    ("特大暴雨", "Extreme rainstorm"),
+    ("强阵雨", "Heavy showers"),
+    ("强雷阵雨", "Severe thunderstorm"),
+    ("极端降雨", "Extreme rain"),
+    ("小雨-中雨", "Light to moderate rain"),
+    ("中雨-大雨", "Moderate to heavy rain"),
+    ("大雨-暴雨", "Heavy rain to rainstorm"),
+    ("暴雨-大暴雨", "Rainstorm to heavy rainstorm"),
+    ("大暴雨-特大暴雨", "Heavy to extreme rainstorm"),
+    ("雨雪天气", "Rain and snow"),
+    ("雨夹雪", "Sleet"),
+    ("阵雨夹雪", "Sleet showers"),
+    ("冻雨", "Freezing rain"),
+    ("雪", "Snow"),
+    ("阵雪", "Snow showers"),
+    ("小雪", "Light snow"),
+    ("中雪", "Moderate snow"),
+    ("大雪", "Heavy snow"),
+    ("暴雪", "Blizzard"),
+    ("雪粒", "Snow grains"),
+    ("小雪-中雪", "Light to moderate snow"),
+    ("中雪-大雪", "Moderate to heavy snow"),
+    ("大雪-暴雪", "Heavy snow to blizzard"),
+    ("浮尘", "Dust"),
+    ("扬沙", "Blowing sand"),
+    ("沙尘暴", "Sandstorm"),
+    ("强沙尘暴", "Severe sandstorm"),
+    ("雾", "Fog"),
+    ("薄雾", "Mist"),
+    ("轻雾", "Mist"),
+    ("浓雾", "Dense fog"),
+    ("强浓雾", "Heavy fog"),
+    ("大雾", "Heavy fog"),
+    ("特强浓雾", "Extreme fog"),
+    ("热", "Hot"),
+    ("冷", "Cold"),
+    ("未知", "Unknown"),
+];
+
+const WEEKS: [(&str, &str); 7] = [
+    ("周一", "Mon"),
+    ("周二", "Tue"),
+    ("周三", "Wed"),
+    ("周四", "Thu"),
+    ("周五", "Fri"),
+    ("周六", "Sat"),
+    ("周日", "Sun"),
+];
+
+impl Lang {
+    /// 翻译中文，没有对应的天气使用天气状况，否则不变
+    pub fn tr(&self, text: &str) -> String {
+        if *self == Lang::Zh {
+            return text.to_owned();
+        }
+
+        if let Some((_, en)) = WORDS.iter().find(|(zh, _)| *zh == text) {
+            return (*en).to_owned();
+        }
+
+        match Condition::of(text) {
+            Some(condition) => condition.name().to_owned(),
+            None => text.to_owned(),
+        }
+    }
+
+    /// `1` 为周一
+    pub fn week(&self, week: &str) -> String {
+        match week.parse::<usize>() {
+            Ok(i @ 1..=7) => match self {
+                Lang::Zh => WEEKS[i - 1].0.to_owned(),
+                Lang::En => WEEKS[i - 1].1.to_owned(),
+            },
+            _ => week.to_owned(),
+        }
+    }
+
+    /// 风向和风力，如 `东风 3级`，`无风向`、`旋转不定` 等不是方向的不加 `风`
+    pub fn wind(&self, wind: &str, power: &str) -> String {
+        match self {
+            Lang::Zh if wind.ends_with(['北', '东', '南', '西']) => {
+                format!("{wind}风 {power}级")
+            }
+            Lang::Zh => format!("{wind} {power}级"),
+            Lang::En => format!("{} force {power}", self.tr(wind)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind() {
+        assert_eq!(Lang::Zh.wind("东北", "3"), "东北风 3级");
+        assert_eq!(Lang::Zh.wind("东北风", "3"), "东北风 3级");
+        assert_eq!(Lang::Zh.wind("无风向", "≤3"), "无风向 ≤3级");
+        assert_eq!(Lang::Zh.wind("旋转不定", "1"), "旋转不定 1级");
+        assert_eq!(Lang::En.wind("旋转不定", "1"), "Variable force 1");
+    }
+}
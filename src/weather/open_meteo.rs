@@ -0,0 +1,327 @@
+use serde::Deserialize;
+
+use super::{
+    endpoint, geo::Place, get_json, night_icon, week_of, wind_direction, wind_power, Forecast,
+    Live, Provider,
+};
+use crate::Result;
+
+const HOST: &str = "https://api.open-meteo.com";
+const GEO_HOST: &str = "https://geocoding-api.open-meteo.com";
+
+/// open-meteo.com，免费无需 key，城市为名称或 `纬度,经度`
+pub(super) struct OpenMeteo {
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeoResult {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    admin1: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeoData {
+    #[serde(default)]
+    results: Vec<GeoResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Daily {
+    time: Vec<String>,
+    weather_code: Vec<Option<u8>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    wind_speed_10m_max: Vec<Option<f64>>,
+    wind_direction_10m_dominant: Vec<Option<f64>>,
+    /// `2024-05-01T05:58`
+    #[serde(default)]
+    sunrise: Vec<String>,
+    #[serde(default)]
+    sunset: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastData {
+    daily: Daily,
+}
+
+#[derive(Debug, Deserialize)]
+struct Current {
+    /// `2024-05-01T20:00`
+    time: String,
+    temperature_2m: f64,
+    relative_humidity_2m: f64,
+    apparent_temperature: f64,
+    weather_code: u8,
+    wind_speed_10m: f64,
+    wind_direction_10m: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentData {
+    current: Current,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimezoneData {
+    utc_offset_seconds: i32,
+}
+
+impl OpenMeteo {
+    fn geocode(&self, name: &str, count: usize) -> Result<Vec<GeoResult>> {
+        let url = endpoint(&self.base_url, GEO_HOST, "/v1/search");
+        let data: GeoData = get_json(
+            &url,
+            &[
+                ("name", name),
+                ("count", &count.to_string()),
+                ("language", "zh"),
+            ],
+        )?;
+        Ok(data.results)
+    }
+
+    /// 坐标所在时区当前的 UTC 偏移秒数
+    pub fn utc_offset(&self, (lat, lon): (f64, f64)) -> Result<i32> {
+        let url = endpoint(&self.base_url, HOST, "/v1/forecast");
+        let data: TimezoneData = get_json(
+            &url,
+            &[
+                ("latitude", &lat.to_string()),
+                ("longitude", &lon.to_string()),
+                ("current", "is_day"),
+                ("timezone", "auto"),
+            ],
+        )?;
+        Ok(data.utc_offset_seconds)
+    }
+}
+
+impl Provider for OpenMeteo {
+    fn forecast(&self, city: &str) -> Result<Vec<Forecast>> {
+        let (lat, lon) = self.coordinates(city)?;
+
+        let url = endpoint(&self.base_url, HOST, "/v1/forecast");
+        let data: ForecastData = get_json(
+            &url,
+            &[
+                ("latitude", &lat.to_string()),
+                ("longitude", &lon.to_string()),
+                (
+                    "daily",
+                    "weather_code,temperature_2m_max,temperature_2m_min,wind_speed_10m_max,wind_direction_10m_dominant,sunrise,sunset",
+                ),
+                ("timezone", "auto"),
+            ],
+        )?;
+
+        // 超出模型范围的日期为 null，没有天气和温度时跳过
+        let daily = data.daily;
+        let casts = daily
+            .time
+            .iter()
+            .enumerate()
+            .filter_map(|(i, date)| {
+                let code = at(&daily.weather_code, i)?;
+                let max = at(&daily.temperature_2m_max, i)?;
+                let min = at(&daily.temperature_2m_min, i)?;
+
+                let (weather, icon) = weather_code(code);
+                let wind = at(&daily.wind_direction_10m_dominant, i)
+                    .map(wind_direction)
+                    .unwrap_or_default();
+                let power = at(&daily.wind_speed_10m_max, i)
+                    .map(wind_power)
+                    .unwrap_or_default();
+                let hour_minute = |times: &[String]| {
+                    times
+                        .get(i)
+                        .and_then(|t| t.split_once('T'))
+                        .map(|(_, t)| t.to_owned())
+                };
+
+                // 日数据没有夜间天气，夜间使用同一天气和最低温度
+                Some(Forecast {
+                    date: date.clone(),
+                    week: week_of(date),
+                    day_icon: icon.to_owned(),
+                    day_weather: weather.to_owned(),
+                    day_temp: format!("{max:.0}"),
+                    day_wind: wind.clone(),
+                    day_power: power.clone(),
+                    day_temp_float: max,
+                    night_icon: night_icon(icon).to_owned(),
+                    night_weather: weather.to_owned(),
+                    night_temp: format!("{min:.0}"),
+                    night_wind: wind,
+                    night_power: power,
+                    night_temp_float: min,
+                    sunrise: hour_minute(&daily.sunrise),
+                    sunset: hour_minute(&daily.sunset),
+                })
+            })
+            .collect();
+
+        Ok(casts)
+    }
+
+    fn live(&self, city: &str) -> Result<Live> {
+        let (lat, lon) = self.coordinates(city)?;
+
+        let url = endpoint(&self.base_url, HOST, "/v1/forecast");
+        let data: CurrentData = get_json(
+            &url,
+            &[
+                ("latitude", &lat.to_string()),
+                ("longitude", &lon.to_string()),
+                (
+                    "current",
+                    "temperature_2m,relative_humidity_2m,apparent_temperature,weather_code,wind_speed_10m,wind_direction_10m",
+                ),
+                ("timezone", "auto"),
+            ],
+        )?;
+
+        let current = data.current;
+        let (weather, icon) = weather_code(current.weather_code);
+
+        Ok(Live {
+            city: city.to_owned(),
+            icon: icon.to_owned(),
+            weather: weather.to_owned(),
+            temp: format!("{:.0}", current.temperature_2m),
+            temp_float: current.temperature_2m,
+            feels_like: Some(current.apparent_temperature),
+            humidity: format!("{:.0}", current.relative_humidity_2m),
+            wind: wind_direction(current.wind_direction_10m),
+            power: wind_power(current.wind_speed_10m),
+            report_time: current.time.replace('T', " "),
+        })
+    }
+
+    fn search(&self, name: &str) -> Result<Vec<Place>> {
+        let places = self
+            .geocode(name, 5)?
+            .into_iter()
+            .map(|r| Place {
+                region: [r.admin1, r.country]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                city: format!("{:.4},{:.4}", r.latitude, r.longitude),
+                coordinates: Some((r.latitude, r.longitude)),
+                name: r.name,
+            })
+            .collect();
+
+        Ok(places)
+    }
+}
+
+fn at<T: Copy>(values: &[Option<T>], i: usize) -> Option<T> {
+    values.get(i).copied().flatten()
+}
+
+/// WMO 天气代码
+fn weather_code(code: u8) -> (&'static str, &'static str) {
+    match code {
+        0 => ("晴", "󰖙"),
+        1 => ("少云", "󰖐"),
+        2 => ("多云", "󰖕"),
+        3 => ("阴", "󰼰"),
+        45 | 48 => ("雾", "󰖑"),
+        51 | 53 | 55 => ("毛毛雨", "󰖒"),
+        56 | 57 | 66 | 67 => ("冻雨", "󰙿"),
+        61 => ("小雨", "󰖒"),
+        63 => ("中雨", "󰖗"),
+        65 => ("大雨", "󰖖"),
+        71 => ("小雪", "󰖘"),
+        73 => ("中雪", "󰖘"),
+        75 => ("大雪", "󰼶"),
+        77 => ("雪粒", "󰖘"),
+        80 | 81 => ("阵雨", "󰖓"),
+        82 => ("强阵雨", "󰖖"),
+        85 | 86 => ("阵雪", "󰼴"),
+        95 => ("雷阵雨", "󰙾"),
+        96 | 99 => ("雷阵雨并伴有冰雹", "󰙾"),
+        _ => ("未知", ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::{condition::Condition, mock};
+
+    const FORECAST: &str = r#"{"daily": {
+        "time": ["2024-05-01", "2024-05-02", "2024-05-03"],
+        "weather_code": [61, 0, null],
+        "temperature_2m_max": [24.6, 28.2, null],
+        "temperature_2m_min": [17.4, 18.0, null],
+        "wind_speed_10m_max": [14.0, null, null],
+        "wind_direction_10m_dominant": [90.0, null, null],
+        "sunrise": ["2024-05-01T05:12", "2024-05-02T05:11", "2024-05-03T05:10"],
+        "sunset": ["2024-05-01T18:40", "2024-05-02T18:41", "2024-05-03T18:42"]
+    }}"#;
+
+    const CURRENT: &str = r#"{"current": {
+        "time": "2024-05-01T20:00",
+        "temperature_2m": 21.4,
+        "relative_humidity_2m": 78.0,
+        "apparent_temperature": 22.9,
+        "weather_code": 95,
+        "wind_speed_10m": 3.0,
+        "wind_direction_10m": 200.0
+    }}"#;
+
+    fn provider() -> OpenMeteo {
+        let base_url = mock::serve(&[("daily=", FORECAST), ("current=", CURRENT)]);
+        OpenMeteo {
+            base_url: Some(base_url),
+        }
+    }
+
+    #[test]
+    fn forecast() {
+        let casts = provider().forecast("31.23,121.47").unwrap();
+        // 第三天没有数据，不显示为 0°
+        assert_eq!(casts.len(), 2);
+
+        let today = &casts[0];
+        assert_eq!(today.week, "3");
+        assert_eq!(today.day_weather, "小雨");
+        assert_eq!(today.day_icon, "󰖒");
+        assert_eq!(today.day_temp, "25");
+        assert_eq!(today.night_temp, "17");
+        assert_eq!(today.day_wind, "东");
+        assert_eq!(today.sunrise.as_deref(), Some("05:12"));
+        assert_eq!(Condition::of(&today.day_weather), Some(Condition::Rain));
+
+        let tomorrow = &casts[1];
+        assert_eq!(Condition::of(&tomorrow.day_weather), Some(Condition::Sunny));
+        assert_eq!(tomorrow.day_icon, "󰖙");
+        assert_eq!(tomorrow.night_icon, "󰖔");
+        assert_eq!(tomorrow.day_wind, "");
+        assert_eq!(tomorrow.day_power, "");
+    }
+
+    #[test]
+    fn live() {
+        let live = provider().live("31.23,121.47").unwrap();
+        assert_eq!(live.weather, "雷阵雨");
+        assert_eq!(live.icon, "󰙾");
+        assert_eq!(Condition::of(&live.weather), Some(Condition::Storm));
+        assert_eq!(live.temp, "21");
+        assert_eq!(live.feels_like, Some(22.9));
+        assert_eq!(live.humidity, "78");
+        assert_eq!(live.wind, "南");
+        assert_eq!(live.report_time, "2024-05-01 20:00");
+    }
+}
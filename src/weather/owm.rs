@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Timelike};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{
+    alert::Alert, endpoint, geo, geo::Place, get_json, night_icon, week_of, wind_direction,
+    wind_power, Forecast, Live, Provider,
+};
+use crate::Result;
+
+const HOST: &str = "https://api.openweathermap.org";
+
+/// openweathermap.org，5 天 3 小时预报，需要 key
+pub(super) struct Owm {
+    pub key: String,
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Main {
+    temp_min: f64,
+    temp_max: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Condition {
+    id: u16,
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Wind {
+    /// m/s
+    speed: f64,
+    deg: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    dt: i64,
+    main: Main,
+    weather: Vec<Condition>,
+    wind: Wind,
+}
+
+#[derive(Debug, Deserialize)]
+struct City {
+    /// 时区偏移秒数
+    timezone: i64,
+    /// 今天的日出日落
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastData {
+    list: Vec<Item>,
+    city: City,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentMain {
+    temp: f64,
+    feels_like: f64,
+    humidity: f64,
+}
+
+/// 当前天气
+#[derive(Debug, Deserialize)]
+struct Current {
+    dt: i64,
+    timezone: i64,
+    name: String,
+    main: CurrentMain,
+    weather: Vec<Condition>,
+    wind: Wind,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeoResult {
+    name: String,
+    #[serde(default)]
+    local_names: HashMap<String, String>,
+    lat: f64,
+    lon: f64,
+    country: String,
+    #[serde(default)]
+    state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneCallAlert {
+    sender_name: String,
+    event: String,
+    start: i64,
+    end: i64,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneCallData {
+    #[serde(default)]
+    alerts: Vec<OneCallAlert>,
+}
+
+impl Item {
+    fn weather(&self) -> (String, &'static str) {
+        match self.weather.first() {
+            Some(c) => (c.description.clone(), weather_icon(c.id)),
+            None => (String::new(), ""),
+        }
+    }
+
+    fn wind(&self) -> (String, String) {
+        (
+            wind_direction(self.wind.deg),
+            wind_power(self.wind.speed * 3.6),
+        )
+    }
+}
+
+impl Owm {
+    /// 城市为名称或 `纬度,经度`
+    fn get<T: DeserializeOwned>(&self, path: &str, city: &str) -> Result<T> {
+        let url = endpoint(&self.base_url, HOST, path);
+
+        let mut query = vec![
+            ("appid", self.key.as_str()),
+            ("units", "metric"),
+            ("lang", "zh_cn"),
+        ];
+        let coordinates =
+            geo::coordinates(city).map(|(lat, lon)| (lat.to_string(), lon.to_string()));
+        match &coordinates {
+            Some((lat, lon)) => {
+                query.push(("lat", lat));
+                query.push(("lon", lon));
+            }
+            None => query.push(("q", city)),
+        }
+
+        get_json(&url, &query)
+    }
+}
+
+impl Provider for Owm {
+    fn forecast(&self, city: &str) -> Result<Vec<Forecast>> {
+        let data: ForecastData = self.get("/data/2.5/forecast", city)?;
+
+        // 按当地日期分组，(日期, 小时, 条目)
+        let mut days: Vec<(String, Vec<(u32, &Item)>)> = Vec::new();
+        for item in &data.list {
+            let Some(time) = DateTime::from_timestamp(item.dt + data.city.timezone, 0) else {
+                continue;
+            };
+            let date = time.format("%Y-%m-%d").to_string();
+            let hour = time.hour();
+
+            match days.last_mut() {
+                Some((d, items)) if *d == date => items.push((hour, item)),
+                _ => days.push((date, vec![(hour, item)])),
+            }
+        }
+
+        let local = |t: Option<i64>| DateTime::from_timestamp(t? + data.city.timezone, 0);
+        let sunrise = local(data.city.sunrise);
+        let sunset = local(data.city.sunset);
+        // 只有今天的日出日落
+        let sun = |date: &str, t: Option<DateTime<_>>| {
+            t.filter(|t| t.format("%Y-%m-%d").to_string() == date)
+                .map(|t| t.format("%H:%M").to_string())
+        };
+
+        let casts = days
+            .iter()
+            .filter_map(|(date, items)| {
+                let day = closest(items, 12)?;
+                let night = closest(items, 21)?;
+
+                let max = items
+                    .iter()
+                    .map(|(_, i)| i.main.temp_max)
+                    .fold(f64::MIN, f64::max);
+                let min = items
+                    .iter()
+                    .map(|(_, i)| i.main.temp_min)
+                    .fold(f64::MAX, f64::min);
+
+                let (day_weather, day_icon) = day.weather();
+                let (night_weather, icon) = night.weather();
+                let (day_wind, day_power) = day.wind();
+                let (night_wind, night_power) = night.wind();
+
+                Some(Forecast {
+                    date: date.clone(),
+                    week: week_of(date),
+                    day_icon: day_icon.to_owned(),
+                    day_weather,
+                    day_temp: format!("{max:.0}"),
+                    day_wind,
+                    day_power,
+                    day_temp_float: max,
+                    night_icon: night_icon(icon).to_owned(),
+                    night_weather,
+                    night_temp: format!("{min:.0}"),
+                    night_wind,
+                    night_power,
+                    night_temp_float: min,
+                    sunrise: sun(date, sunrise),
+                    sunset: sun(date, sunset),
+                })
+            })
+            .collect();
+
+        Ok(casts)
+    }
+
+    fn live(&self, city: &str) -> Result<Live> {
+        let data: Current = self.get("/data/2.5/weather", city)?;
+
+        let (weather, icon) = match data.weather.first() {
+            Some(c) => (c.description.clone(), weather_icon(c.id)),
+            None => (String::new(), ""),
+        };
+        let report_time = DateTime::from_timestamp(data.dt + data.timezone, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+
+        Ok(Live {
+            city: data.name,
+            icon: icon.to_owned(),
+            weather,
+            temp: format!("{:.0}", data.main.temp),
+            temp_float: data.main.temp,
+            feels_like: Some(data.main.feels_like),
+            humidity: format!("{:.0}", data.main.humidity),
+            wind: wind_direction(data.wind.deg),
+            power: wind_power(data.wind.speed * 3.6),
+            report_time,
+        })
+    }
+
+    /// One Call 3.0，需要单独订阅
+    fn alerts(&self, city: &str) -> Result<Vec<Alert>> {
+        let (lat, lon) = self.coordinates(city)?;
+
+        let url = endpoint(&self.base_url, HOST, "/data/3.0/onecall");
+        let data: OneCallData = get_json(
+            &url,
+            &[
+                ("appid", self.key.as_str()),
+                ("lat", &lat.to_string()),
+                ("lon", &lon.to_string()),
+                ("exclude", "current,minutely,hourly,daily"),
+                ("lang", "zh_cn"),
+            ],
+        )?;
+
+        let alerts = data
+            .alerts
+            .into_iter()
+            .map(|a| Alert {
+                id: format!("{}-{}-{}", a.sender_name, a.event, a.start),
+                event: a.event,
+                sender: a.sender_name,
+                start: a.start,
+                end: a.end,
+                description: a.description,
+            })
+            .collect();
+
+        Ok(alerts)
+    }
+
+    fn search(&self, name: &str) -> Result<Vec<Place>> {
+        let url = endpoint(&self.base_url, HOST, "/geo/1.0/direct");
+        let data: Vec<GeoResult> =
+            get_json(&url, &[("appid", &self.key), ("q", name), ("limit", "5")])?;
+
+        let places = data
+            .into_iter()
+            .map(|r| Place {
+                region: [r.state, Some(r.country)]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                city: format!("{:.4},{:.4}", r.lat, r.lon),
+                coordinates: Some((r.lat, r.lon)),
+                name: r.local_names.get("zh").cloned().unwrap_or(r.name),
+            })
+            .collect();
+
+        Ok(places)
+    }
+}
+
+/// 最接近 `hour` 的条目
+fn closest<'a>(items: &[(u32, &'a Item)], hour: u32) -> Option<&'a Item> {
+    items
+        .iter()
+        .min_by_key(|(h, _)| h.abs_diff(hour))
+        .map(|(_, item)| *item)
+}
+
+/// openweathermap 天气代码
+fn weather_icon(id: u16) -> &'static str {
+    match id {
+        200..=299 => "󰙾",
+        300..=399 | 500 => "󰖒",
+        501 => "󰖗",
+        502..=504 => "󰖖",
+        511 | 611..=616 => "󰙿",
+        520..=531 => "󰖓",
+        600 | 601 => "󰖘",
+        602 => "󰼶",
+        620..=622 => "󰼴",
+        701 | 711 | 721 | 741 => "󰖑",
+        731 | 751 | 761 | 762 => "",
+        771 => "󰖝",
+        781 => "󰢘",
+        800 => "󰖙",
+        801 => "󰖐",
+        802 => "󰖕",
+        803 => "󰼯",
+        804 => "󰼰",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::{condition::Condition, mock};
+
+    /// 北京时间，第二天 21 点的条目没有 weather
+    const FORECAST: &str = r#"{
+        "city": {"timezone": 28800, "sunrise": 1714512720, "sunset": 1714560000},
+        "list": [
+            {"dt": 1714536000, "main": {"temp_min": 20.1, "temp_max": 24.6},
+             "weather": [{"id": 501, "description": "中雨"}], "wind": {"speed": 3.0, "deg": 90}},
+            {"dt": 1714568400, "main": {"temp_min": 16.8, "temp_max": 19.0},
+             "weather": [{"id": 800, "description": "晴"}], "wind": {"speed": 1.0, "deg": 0}},
+            {"dt": 1714622400, "main": {"temp_min": 21.0, "temp_max": 28.0},
+             "weather": [{"id": 211, "description": "雷暴"}], "wind": {"speed": 5.0, "deg": 180}},
+            {"dt": 1714654800, "main": {"temp_min": 18.0, "temp_max": 20.0},
+             "weather": [], "wind": {"speed": 2.0, "deg": 270}}
+        ]
+    }"#;
+
+    const CURRENT: &str = r#"{"dt": 1714564800, "timezone": 28800, "name": "上海",
+        "main": {"temp": 21.6, "feels_like": 21.9, "humidity": 73},
+        "weather": [{"id": 741, "description": "雾"}], "wind": {"speed": 2.5, "deg": 135}}"#;
+
+    fn provider() -> Owm {
+        let base_url = mock::serve(&[
+            ("/data/2.5/forecast", FORECAST),
+            ("/data/2.5/weather", CURRENT),
+        ]);
+        Owm {
+            key: "test".to_owned(),
+            base_url: Some(base_url),
+        }
+    }
+
+    #[test]
+    fn forecast() {
+        let casts = provider().forecast("31.23,121.47").unwrap();
+        assert_eq!(casts.len(), 2);
+
+        let today = &casts[0];
+        assert_eq!(today.date, "2024-05-01");
+        assert_eq!(today.day_weather, "中雨");
+        assert_eq!(today.day_icon, "󰖗");
+        assert_eq!(Condition::of(&today.day_weather), Some(Condition::Rain));
+        assert_eq!(today.night_icon, "󰖔");
+        assert_eq!(today.day_temp, "25");
+        assert_eq!(today.night_temp, "17");
+        assert_eq!(today.sunrise.as_deref(), Some("05:32"));
+        assert_eq!(today.sunset.as_deref(), Some("18:40"));
+
+        let tomorrow = &casts[1];
+        assert_eq!(tomorrow.day_icon, "󰙾");
+        assert_eq!(Condition::of(&tomorrow.day_weather), Some(Condition::Storm));
+        // 只有今天的日出日落，缺少 weather 时为空
+        assert_eq!(tomorrow.sunrise, None);
+        assert_eq!(tomorrow.night_weather, "");
+        assert_eq!(tomorrow.night_icon, "");
+    }
+
+    #[test]
+    fn live() {
+        let live = provider().live("31.23,121.47").unwrap();
+        assert_eq!(live.city, "上海");
+        assert_eq!(live.icon, "󰖑");
+        assert_eq!(Condition::of(&live.weather), Some(Condition::Fog));
+        assert_eq!(live.temp, "22");
+        assert_eq!(live.feels_like, Some(21.9));
+        assert_eq!(live.wind, "东南");
+        assert_eq!(live.report_time, "2024-05-01 20:00");
+    }
+}
@@ -0,0 +1,394 @@
+use std::f64::consts::PI;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    geo, open_meteo::OpenMeteo, unsupported, Client, Forecast, Period, Provider, Values,
+    WeatherArgs,
+};
+use crate::{errors::Error, utils::render, waybar::WaybarData, Result};
+
+/// 儒略日 2000-01-01 12:00 UTC
+const J2000: f64 = 2451545.0;
+/// 朔望月，天
+const SYNODIC_MONTH: f64 = 29.530588853;
+/// 2000-01-06 18:14 UTC 新月的儒略日
+const NEW_MOON: f64 = 2451550.26;
+
+/// 月相名称和图标，从新月开始
+const PHASES: [(&str, &str); 8] = [
+    ("新月", "󰽤"),
+    ("娥眉月", "󰽧"),
+    ("上弦月", "󰽡"),
+    ("盈凸月", "󰽨"),
+    ("满月", "󰽢"),
+    ("亏凸月", "󰽦"),
+    ("下弦月", "󰽣"),
+    ("残月", "󰽥"),
+];
+
+/// 日出日落和月相，根据坐标离线计算，时间为城市所在时区
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct Sun {
+    pub date: String,
+    /// `HH:MM`，极昼极夜时为空
+    pub sunrise: Option<String>,
+    pub sunset: Option<String>,
+    /// 昼长 `H:MM`
+    pub day_length: String,
+    /// 新月、娥眉月 ..
+    pub moon: String,
+    pub moon_icon: String,
+    /// 月面照亮比例 %
+    pub illumination: u32,
+    /// 时间的时区，如 `+08:00`
+    pub utc_offset: String,
+}
+
+impl Sun {
+    pub fn new(date: NaiveDate, (lat, lon): (f64, f64), offset: FixedOffset) -> Self {
+        let (sunrise, sunset, minutes) = match sun_times(date, lat, lon) {
+            Times::Rise(rise, set) => (
+                Some(clock_time(rise, offset)),
+                Some(clock_time(set, offset)),
+                ((set - rise) * 1440.0).round() as u32,
+            ),
+            Times::PolarDay => (None, None, 1440),
+            Times::PolarNight => (None, None, 0),
+        };
+
+        // 当天中午的月相
+        let noon = julian_day(date) + 0.5;
+        let (phase, illumination) = moon_phase(noon);
+        let (moon, moon_icon) = PHASES[phase];
+
+        Sun {
+            date: date.format("%Y-%m-%d").to_string(),
+            sunrise,
+            sunset,
+            day_length: format!("{}:{:02}", minutes / 60, minutes % 60),
+            moon: moon.to_owned(),
+            moon_icon: moon_icon.to_owned(),
+            illumination: (illumination * 100.0).round() as u32,
+            utc_offset: offset.to_string(),
+        }
+    }
+
+    pub fn values(&self, args: &WeatherArgs) -> Values {
+        vec![
+            ("sunrise", self.sunrise.clone().unwrap_or_default()),
+            ("sunset", self.sunset.clone().unwrap_or_default()),
+            ("day_length", self.day_length.clone()),
+            ("moon", args.lang.tr(&self.moon)),
+            ("moon_icon", self.moon_icon.clone()),
+            ("utc_offset", self.utc_offset.clone()),
+        ]
+    }
+
+    pub fn tooltip(&self, args: &WeatherArgs) -> String {
+        let lang = args.lang;
+        let mut lines = Vec::new();
+        if let (Some(sunrise), Some(sunset)) = (&self.sunrise, &self.sunset) {
+            lines.push(format!(
+                "{} {sunrise} {} {sunset} (UTC{})",
+                lang.tr("日出"),
+                lang.tr("日落"),
+                self.utc_offset
+            ));
+        }
+        lines.push(format!("{} {}", lang.tr("昼长"), self.day_length));
+        lines.push(format!(
+            "{} {} {} {}%",
+            lang.tr("月相"),
+            self.moon_icon,
+            lang.tr(&self.moon),
+            self.illumination
+        ));
+        lines.join("\n")
+    }
+}
+
+enum Times {
+    /// 日出日落的儒略日
+    Rise(f64, f64),
+    PolarDay,
+    PolarNight,
+}
+
+/// 日出方程，精度约 1 分钟
+fn sun_times(date: NaiveDate, lat: f64, lon: f64) -> Times {
+    let n = (julian_day(date) + 0.5 - J2000 + 0.0008).round();
+    // 平太阳时
+    let mean = n - lon / 360.0;
+    // 平近点角
+    let m = (357.5291 + 0.98560028 * mean)
+        .rem_euclid(360.0)
+        .to_radians();
+    // 中心差
+    let c = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    // 黄经
+    let lambda = (m.to_degrees() + c + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + mean + 0.0053 * m.sin() - 0.0069 * (2.0 * lambda).sin();
+
+    let declination = (lambda.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let lat = lat.to_radians();
+    // 太阳中心低于地平线 0.833° 时为日出日落，包含大气折射和太阳半径
+    let cos_hour = ((-0.833_f64).to_radians().sin() - lat.sin() * declination.sin())
+        / (lat.cos() * declination.cos());
+
+    if cos_hour < -1.0 {
+        return Times::PolarDay;
+    }
+    if cos_hour > 1.0 {
+        return Times::PolarNight;
+    }
+
+    let hour = cos_hour.acos().to_degrees() / 360.0;
+    Times::Rise(transit - hour, transit + hour)
+}
+
+/// 月龄对应的月相序号和照亮比例
+fn moon_phase(jd: f64) -> (usize, f64) {
+    let age = (jd - NEW_MOON).rem_euclid(SYNODIC_MONTH) / SYNODIC_MONTH;
+    let illumination = (1.0 - (2.0 * PI * age).cos()) / 2.0;
+    ((age * 8.0).round() as usize % 8, illumination)
+}
+
+/// 当天 0:00 UTC 的儒略日
+fn julian_day(date: NaiveDate) -> f64 {
+    let days = date
+        .signed_duration_since(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+        .num_days();
+    days as f64 + 2440587.5
+}
+
+/// 儒略日在 `offset` 时区的 `HH:MM`
+fn clock_time(jd: f64, offset: FixedOffset) -> String {
+    let secs = ((jd - 2440587.5) * 86400.0).round() as i64;
+    DateTime::from_timestamp(secs, 0)
+        .map(|t| t.with_timezone(&offset).format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+impl Client {
+    /// 城市坐标，结果一直缓存。数据源不能查询时使用 open-meteo 查询
+    pub(super) fn coordinates(&self, city: &str) -> Result<(f64, f64)> {
+        if let Some(coordinates) = geo::coordinates(city) {
+            return Ok(coordinates);
+        }
+
+        let name = format!("{city}-coordinates");
+        if let Some(coordinates) = self.cache.load(&name) {
+            return Ok(coordinates);
+        }
+
+        let coordinates = match self.provider.coordinates(city) {
+            Err(err) if unsupported(err.as_ref()) => OpenMeteo {
+                base_url: self.open_meteo_url(),
+            }
+            .coordinates(city)?,
+            coordinates => coordinates?,
+        };
+        self.cache.save(&name, &coordinates);
+
+        Ok(coordinates)
+    }
+
+    /// 城市所在时区当前的偏移，通过 open-meteo 查询，按 ttl 缓存以跟随夏令时。
+    /// 查询失败时为本地时区
+    fn utc_offset(&self, city: &str, coordinates: (f64, f64)) -> FixedOffset {
+        let open_meteo = OpenMeteo {
+            base_url: self.open_meteo_url(),
+        };
+        let offset = self.cache.fetch(&format!("{city}-utc-offset"), || {
+            open_meteo.utc_offset(coordinates)
+        });
+
+        match offset.map(FixedOffset::east_opt) {
+            Ok(Some(offset)) => offset,
+            Ok(None) => Local::now().offset().fix(),
+            Err(err) => {
+                log::warn!("Failed to get time zone of {city}, use local time: {err}");
+                Local::now().offset().fix()
+            }
+        }
+    }
+
+    /// 城市当前时间，用于 `--period auto` 与城市时区的日出日落比较。
+    /// 其他时段或没有坐标时为本地时间
+    pub(super) fn now(&self, args: &WeatherArgs, city: &str) -> NaiveDateTime {
+        if args.period != Period::Auto {
+            return Local::now().naive_local();
+        }
+
+        match self.place(city) {
+            Ok((_, offset)) => Utc::now().with_timezone(&offset).naive_local(),
+            Err(err) => {
+                log::info!("Failed to get time zone of {city}, use local time: {err}");
+                Local::now().naive_local()
+            }
+        }
+    }
+
+    /// 坐标和时区
+    fn place(&self, city: &str) -> Result<((f64, f64), FixedOffset)> {
+        let coordinates = self.coordinates(city)?;
+        Ok((coordinates, self.utc_offset(city, coordinates)))
+    }
+
+    /// `date` 为 `YYYY-MM-DD`
+    pub(super) fn sun(&self, city: &str, date: &str) -> Result<Sun> {
+        let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+            return Error::WeatherFailError.into();
+        };
+        let (coordinates, offset) = self.place(city)?;
+        Ok(Sun::new(date, coordinates, offset))
+    }
+
+    /// 数据源没有日出日落时（如高德）计算，用于白天夜间和 tooltip
+    pub(super) fn fill_sun(&self, city: &str, casts: &mut [Forecast]) {
+        if casts.iter().all(|c| c.sunrise.is_some()) {
+            return;
+        }
+        let (coordinates, offset) = match self.place(city) {
+            Ok(place) => place,
+            Err(err) => {
+                log::info!("Failed to compute sunrise: {}", err);
+                return;
+            }
+        };
+
+        for cast in casts.iter_mut().filter(|c| c.sunrise.is_none()) {
+            if let Ok(date) = NaiveDate::parse_from_str(&cast.date, "%Y-%m-%d") {
+                let sun = Sun::new(date, coordinates, offset);
+                cast.sunrise = sun.sunrise;
+                cast.sunset = sun.sunset;
+            }
+        }
+    }
+
+    /// 模板中有日出日落或月相时添加模板变量
+    pub(super) fn sun_values(
+        &self,
+        args: &WeatherArgs,
+        city: &str,
+        date: &str,
+        values: &mut Values,
+    ) {
+        const NAMES: [&str; 4] = ["{sunrise", "{sunset", "{day_length", "{moon"];
+
+        let templates = [&args.format, &args.tooltip_format];
+        let in_template = templates.iter().any(|t| {
+            t.as_deref()
+                .is_some_and(|t| NAMES.iter().any(|name| t.contains(name)))
+        });
+        if !in_template {
+            return;
+        }
+
+        match self.sun(city, date) {
+            Ok(sun) => values.extend(sun.values(args)),
+            Err(err) => log::warn!("Failed to compute sunrise: {}", err),
+        }
+    }
+}
+
+/// 城市今天的日出日落和月相
+pub(super) fn print(args: &WeatherArgs, client: &Client, city: &str) -> Result<()> {
+    let (coordinates, offset) = client.place(city)?;
+    let now = Utc::now().with_timezone(&offset);
+    let sun = Sun::new(now.date_naive(), coordinates, offset);
+
+    if args.waybar {
+        let period = args.period.at(now.time(), &sun.sunrise, &sun.sunset);
+        let data = WaybarData {
+            class: format!("wayinfo-weather-sun {}", period.as_str()),
+            text: format!(
+                "{} 󰖜 {} 󰖛 {}",
+                sun.moon_icon,
+                sun.sunrise.as_deref().unwrap_or("--:--"),
+                sun.sunset.as_deref().unwrap_or("--:--")
+            ),
+            alt: Some(period.as_str().to_owned()),
+            tooltip: Some(sun.tooltip(args)),
+            percentage: Some(sun.illumination as usize),
+        };
+        let re = serde_json::to_string(&data).unwrap();
+        println!("{}", re);
+    } else if let Some(format) = &args.format {
+        println!("{}", render(format, &sun.values(args)));
+    } else {
+        let re = serde_json::to_string(&sun).unwrap();
+        println!("{}", re);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::mock;
+
+    fn sun(date: &str, coordinates: (f64, f64), hours: i32) -> Sun {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Sun::new(
+            date,
+            coordinates,
+            FixedOffset::east_opt(hours * 3600).unwrap(),
+        )
+    }
+
+    #[test]
+    fn shanghai() {
+        let sun = sun("2024-06-21", (31.23, 121.47), 8);
+        assert_eq!(sun.sunrise.as_deref(), Some("04:50"));
+        assert_eq!(sun.sunset.as_deref(), Some("19:01"));
+        assert_eq!(sun.utc_offset, "+08:00");
+    }
+
+    #[test]
+    fn new_york() {
+        let sun = sun("2024-06-21", (40.71, -74.01), -4);
+        assert_eq!(sun.sunrise.as_deref(), Some("05:25"));
+        assert_eq!(sun.sunset.as_deref(), Some("20:30"));
+        assert_eq!(sun.utc_offset, "-04:00");
+    }
+
+    #[test]
+    fn polar() {
+        let day = sun("2024-06-21", (69.65, 18.96), 2);
+        assert_eq!((day.sunrise, day.sunset), (None, None));
+        assert_eq!(day.day_length, "24:00");
+
+        let night = sun("2024-12-21", (69.65, 18.96), 1);
+        assert_eq!((night.sunrise, night.sunset), (None, None));
+        assert_eq!(night.day_length, "0:00");
+    }
+
+    #[test]
+    fn moon() {
+        let new = sun("2024-01-11", (31.23, 121.47), 8);
+        assert_eq!(new.moon, "新月");
+        assert!(new.illumination < 5);
+
+        let full = sun("2024-01-25", (31.23, 121.47), 8);
+        assert_eq!(full.moon, "满月");
+        assert!(full.illumination > 95);
+    }
+
+    #[test]
+    fn city_offset() {
+        let base_url = mock::serve(&[(
+            "timezone=auto",
+            r#"{"utc_offset_seconds": -14400, "timezone": "America/New_York"}"#,
+        )]);
+        let open_meteo = OpenMeteo {
+            base_url: Some(base_url),
+        };
+        assert_eq!(open_meteo.utc_offset((40.71, -74.01)).unwrap(), -14400);
+    }
+}
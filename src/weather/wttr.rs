@@ -0,0 +1,308 @@
+use chrono::{NaiveDateTime, NaiveTime};
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::{
+    endpoint, get_json, night_icon, week_of, wind_direction, wind_power, Forecast, Live, Provider,
+};
+use crate::{errors::Error, Result};
+
+const HOST: &str = "https://wttr.in";
+
+/// wttr.in，免费无需 key
+pub(super) struct Wttr {
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Value {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Hourly {
+    #[serde(default)]
+    time: String,
+    weather_code: String,
+    weather_desc: Vec<Value>,
+    #[serde(default, rename = "lang_zh")]
+    lang_zh: Vec<Value>,
+    windspeed_kmph: String,
+    winddir_degree: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Astronomy {
+    /// `05:58 AM`
+    sunrise: String,
+    sunset: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Day {
+    date: String,
+    #[serde(rename = "maxtempC")]
+    max_temp_c: String,
+    #[serde(rename = "mintempC")]
+    min_temp_c: String,
+    #[serde(default)]
+    astronomy: Vec<Astronomy>,
+    hourly: Vec<Hourly>,
+}
+
+/// 当前天气
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Current {
+    #[serde(flatten)]
+    hourly: Hourly,
+    #[serde(rename = "temp_C")]
+    temp_c: String,
+    #[serde(rename = "FeelsLikeC")]
+    feels_like_c: String,
+    humidity: String,
+    /// `2024-05-01 08:15 PM`
+    local_obs_date_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Area {
+    area_name: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherData {
+    weather: Vec<Day>,
+    #[serde(default)]
+    current_condition: Vec<Current>,
+    #[serde(default)]
+    nearest_area: Vec<Area>,
+}
+
+impl Hourly {
+    fn weather(&self) -> String {
+        self.lang_zh
+            .first()
+            .or(self.weather_desc.first())
+            .map(|v| v.value.trim().to_owned())
+            .unwrap_or_default()
+    }
+
+    fn icon(&self) -> &'static str {
+        weather_icon(self.weather_code.parse().unwrap_or_default())
+    }
+
+    fn wind(&self) -> (String, String) {
+        let deg = self.winddir_degree.parse().unwrap_or_default();
+        let speed = self.windspeed_kmph.parse().unwrap_or_default();
+        (wind_direction(deg), wind_power(speed))
+    }
+}
+
+impl Day {
+    /// 三小时一条，time 为 `0`, `300` .. `2100`
+    fn at(&self, time: &str) -> Option<&Hourly> {
+        self.hourly
+            .iter()
+            .find(|h| h.time == time)
+            .or(self.hourly.last())
+    }
+}
+
+/// `05:58 AM` 转换为 `05:58`
+fn hour_minute(t: &str) -> Option<String> {
+    NaiveTime::parse_from_str(t.trim(), "%I:%M %p")
+        .ok()
+        .map(|t| t.format("%H:%M").to_string())
+}
+
+impl Wttr {
+    fn weather_data(&self, city: &str) -> Result<WeatherData> {
+        let mut url = Url::parse(&endpoint(&self.base_url, HOST, "/"))?;
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().push(city);
+        }
+
+        get_json(url.as_str(), &[("format", "j1"), ("lang", "zh")])
+    }
+}
+
+impl Provider for Wttr {
+    fn forecast(&self, city: &str) -> Result<Vec<Forecast>> {
+        let data = self.weather_data(city)?;
+
+        let casts = data
+            .weather
+            .iter()
+            .filter_map(|day| {
+                let noon = day.at("1200")?;
+                let night = day.at("2100")?;
+                let (day_wind, day_power) = noon.wind();
+                let (night_wind, night_power) = night.wind();
+
+                Some(Forecast {
+                    date: day.date.clone(),
+                    week: week_of(&day.date),
+                    day_icon: noon.icon().to_owned(),
+                    day_weather: noon.weather(),
+                    day_temp: day.max_temp_c.clone(),
+                    day_wind,
+                    day_power,
+                    day_temp_float: day.max_temp_c.parse().unwrap_or_default(),
+                    night_icon: night_icon(night.icon()).to_owned(),
+                    night_weather: night.weather(),
+                    night_temp: day.min_temp_c.clone(),
+                    night_wind,
+                    night_power,
+                    night_temp_float: day.min_temp_c.parse().unwrap_or_default(),
+                    sunrise: day.astronomy.first().and_then(|a| hour_minute(&a.sunrise)),
+                    sunset: day.astronomy.first().and_then(|a| hour_minute(&a.sunset)),
+                })
+            })
+            .collect();
+
+        Ok(casts)
+    }
+
+    fn live(&self, city: &str) -> Result<Live> {
+        let mut data = self.weather_data(city)?;
+        let Some(current) = data.current_condition.pop() else {
+            return Error::WeatherFailError.into();
+        };
+
+        let (wind, power) = current.hourly.wind();
+        let report_time =
+            NaiveDateTime::parse_from_str(&current.local_obs_date_time, "%Y-%m-%d %I:%M %p")
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or(current.local_obs_date_time);
+        let area = data
+            .nearest_area
+            .first()
+            .and_then(|a| a.area_name.first())
+            .map(|v| v.value.clone());
+
+        Ok(Live {
+            city: area.unwrap_or_else(|| city.to_owned()),
+            icon: current.hourly.icon().to_owned(),
+            weather: current.hourly.weather(),
+            temp_float: current.temp_c.parse().unwrap_or_default(),
+            temp: current.temp_c,
+            feels_like: current.feels_like_c.parse().ok(),
+            humidity: current.humidity,
+            wind,
+            power,
+            report_time,
+        })
+    }
+}
+
+/// WWO 天气代码
+fn weather_icon(code: u16) -> &'static str {
+    match code {
+        113 => "󰖙",
+        116 => "󰖕",
+        119 => "󰼯",
+        122 => "󰼰",
+        143 | 248 | 260 => "󰖑",
+        176 | 263 | 266 | 293 | 296 | 353 => "󰖒",
+        299 | 302 | 356 => "󰖗",
+        305 | 308 | 359 => "󰖖",
+        179 | 182 | 185 | 281 | 284 | 311 | 314 | 317 | 320 | 350 | 362 | 365 | 374 | 377 => "󰙿",
+        227 | 323 | 326 | 329 | 332 | 368 => "󰖘",
+        230 | 335 | 338 | 371 => "󰼶",
+        200 | 386 | 389 | 392 | 395 => "󰙾",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::{condition::Condition, mock};
+
+    const DATA: &str = r#"{
+        "current_condition": [{
+            "temp_C": "21", "FeelsLikeC": "23", "humidity": "80",
+            "localObsDateTime": "2024-05-01 08:15 PM",
+            "weatherCode": "296", "weatherDesc": [{"value": "Light rain"}],
+            "lang_zh": [{"value": "小雨"}],
+            "windspeedKmph": "11", "winddirDegree": "45"
+        }],
+        "nearest_area": [{"areaName": [{"value": "Shanghai"}]}],
+        "weather": [{
+            "date": "2024-05-01", "maxtempC": "25", "mintempC": "17",
+            "astronomy": [{"sunrise": "05:12 AM", "sunset": "06:40 PM"}],
+            "hourly": [
+                {"time": "1200", "weatherCode": "113", "weatherDesc": [{"value": "Sunny"}],
+                 "lang_zh": [{"value": "晴"}], "windspeedKmph": "11", "winddirDegree": "180"},
+                {"time": "2100", "weatherCode": "116", "weatherDesc": [{"value": "Partly cloudy"}],
+                 "windspeedKmph": "5", "winddirDegree": "270"}
+            ]
+        }, {
+            "date": "2024-05-02", "maxtempC": "28", "mintempC": "18",
+            "hourly": [
+                {"time": "0", "weatherCode": "389", "weatherDesc": [{"value": "Moderate or heavy rain with thunder"}],
+                 "windspeedKmph": "20", "winddirDegree": "90"}
+            ]
+        }]
+    }"#;
+
+    /// 没有 current_condition
+    const NO_CURRENT: &str = r#"{"weather": []}"#;
+
+    fn provider() -> Wttr {
+        let base_url = mock::serve(&[("/Shanghai", DATA), ("/Nowhere", NO_CURRENT)]);
+        Wttr {
+            base_url: Some(base_url),
+        }
+    }
+
+    #[test]
+    fn forecast() {
+        let casts = provider().forecast("Shanghai").unwrap();
+        assert_eq!(casts.len(), 2);
+
+        let today = &casts[0];
+        assert_eq!(today.day_weather, "晴");
+        assert_eq!(today.day_icon, "󰖙");
+        assert_eq!(today.day_temp, "25");
+        assert_eq!(today.day_wind, "南");
+        assert_eq!(today.sunrise.as_deref(), Some("05:12"));
+        assert_eq!(today.sunset.as_deref(), Some("18:40"));
+        // 没有中文时使用英文
+        assert_eq!(today.night_weather, "Partly cloudy");
+        assert_eq!(today.night_icon, "󰼱");
+        assert_eq!(Condition::of(&today.night_weather), Some(Condition::Cloudy));
+
+        // 缺少对应时间和日出日落
+        let tomorrow = &casts[1];
+        assert_eq!(tomorrow.day_icon, "󰙾");
+        assert_eq!(Condition::of(&tomorrow.day_weather), Some(Condition::Storm));
+        assert_eq!(tomorrow.sunrise, None);
+    }
+
+    #[test]
+    fn live() {
+        let live = provider().live("Shanghai").unwrap();
+        assert_eq!(live.city, "Shanghai");
+        assert_eq!(live.weather, "小雨");
+        assert_eq!(live.icon, "󰖒");
+        assert_eq!(Condition::of(&live.weather), Some(Condition::Rain));
+        assert_eq!(live.temp_float, 21.0);
+        assert_eq!(live.feels_like, Some(23.0));
+        assert_eq!(live.wind, "东北");
+        assert_eq!(live.report_time, "2024-05-01 20:15");
+    }
+
+    #[test]
+    fn live_missing() {
+        let err = provider().live("Nowhere").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::WeatherFailError)
+        ));
+    }
+}